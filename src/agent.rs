@@ -0,0 +1,359 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::book::{is_in_book_range, Book};
+use crate::config::Options;
+use crate::display::{show_diagram_saved, show_error_message, show_input_prompt, Renderer};
+use crate::engine::Engine;
+use crate::notation::{diagram_fmt, parse_diagram};
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::protocol::ExternalEngine;
+use crate::{random_coordinates, random_pawn, read_console};
+use crate::state::{GameState, Move};
+use crate::tile::Tile::Empty;
+
+/// What an agent did with its turn. Errors are kept for agents that could not take their turn at all.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Turn {
+    Move(Move),
+    Resign,
+    /// The player set up a new position instead of moving; the game continues from it if the other agent accepts it.
+    LoadPosition(GameState),
+}
+
+/// Anything that can choose moves: a person at a terminal or window, a search engine or another program.
+pub trait Agent: Send {
+    fn name(&self) -> String;
+
+    /// Chooses a move for the turn player of `game_state`, taking no longer than `time_limit` where the agent can control that.
+    fn select_move(&mut self, game_state: &GameState, time_limit: Option<Duration>) -> Result<Turn, &'static str>;
+
+    /// Called once the game is over with the final position and the side that resigned, if any.
+    fn game_over(&mut self, _game_state: &GameState, _resigned: Option<Player>) {}
+
+    /// Called on the other agent before the game continues from a position set up by the turn player. An error refuses the position.
+    fn position_loaded(&mut self, _game_state: &GameState) -> Result<(), &'static str> {
+        return Ok(());
+    }
+}
+
+/// The two agents of a game, one for each side.
+pub struct Pairing {
+    pub order: Box<dyn Agent>,
+    pub chaos: Box<dyn Agent>,
+}
+
+impl Pairing {
+    pub fn new(order: Box<dyn Agent>, chaos: Box<dyn Agent>) -> Self {
+        Self { order, chaos }
+    }
+
+    pub fn agent(&mut self, player: Player) -> &mut Box<dyn Agent> {
+        match player {
+            Order => &mut self.order,
+            Chaos => &mut self.chaos
+        }
+    }
+}
+
+type FinishedMove = (Box<dyn Agent>, Result<Turn, &'static str>);
+
+/// A move being chosen on another thread, so that a window can keep drawing while an agent thinks.
+pub struct PendingMove {
+    handle: JoinHandle<FinishedMove>,
+}
+
+impl PendingMove {
+    pub fn start(mut agent: Box<dyn Agent>, game_state: GameState, time_limit: Option<Duration>) -> Self {
+        let handle = thread::spawn(move || {
+            let result = agent.select_move(&game_state, time_limit);
+            (agent, result)
+        });
+        Self { handle }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.handle.is_finished();
+    }
+
+    /// Waits for the move and hands the agent back.
+    pub fn finish(self) -> (Option<Box<dyn Agent>>, Result<Turn, &'static str>) {
+        match self.handle.join() {
+            Ok((agent, result)) => return (Some(agent), result),
+            Err(_) => return (None, Err("The agent crashed"))
+        }
+    }
+}
+
+/// A person typing moves such as `X A1` into the terminal. Besides moves it understands `resign`, `save <file>`,
+/// which writes the board as a diagram, and `load <file>`, which continues the game from one.
+pub struct TerminalHuman {
+    renderer: Renderer,
+}
+
+impl TerminalHuman {
+    pub fn new(renderer: Renderer) -> Self {
+        return Self { renderer };
+    }
+}
+
+impl Agent for TerminalHuman {
+    fn name(&self) -> String {
+        return "Human".to_string();
+    }
+
+    fn select_move(&mut self, game_state: &GameState, _time_limit: Option<Duration>) -> Result<Turn, &'static str> {
+        let renderer = &self.renderer;
+        let language = renderer.language;
+        loop {
+            show_input_prompt(renderer);
+            let user_input = read_console()?;
+            if user_input.trim().eq_ignore_ascii_case("resign") {
+                return Ok(Turn::Resign);
+            }
+            if let Some(path) = user_input.trim().strip_prefix("save ") {
+                match fs::write(path.trim(), diagram_fmt(game_state, true)) {
+                    Ok(_) => show_diagram_saved(renderer, path.trim()),
+                    Err(_) => show_error_message(renderer, "Could not write the diagram")
+                }
+                continue;
+            }
+            if let Some(path) = user_input.trim().strip_prefix("load ") {
+                match fs::read_to_string(path.trim()).map_err(|_| "Could not read the diagram").and_then(|text| parse_diagram(&text)) {
+                    Ok(position) => return Ok(Turn::LoadPosition(position)),
+                    Err(v) => show_error_message(renderer, v)
+                }
+                continue;
+            }
+            let (coordinates, tile) = match language.parse_move(&user_input) {
+                Ok(v) => v,
+                Err(_) => {
+                    show_error_message(renderer, &language.format("None of the Regular Expressions matched the input: {}", &[&user_input.trim()]));
+                    continue;
+                }
+            };
+            let mut next_state = *game_state;
+            match next_state.play(coordinates, tile) {
+                Ok(_) => return Ok(Turn::Move((coordinates, tile))),
+                Err(v) => show_error_message(renderer, &language.format("That was not a legal move. Message: {}", &[&language.text(v)]))
+            }
+        }
+    }
+}
+
+/// A person clicking on the board of the graphical interface. The window sends the clicked moves through the paired `Sender`.
+pub struct GuiHuman {
+    moves: Receiver<Move>,
+}
+
+impl GuiHuman {
+    pub fn new() -> (Self, Sender<Move>) {
+        let (sender, moves) = channel();
+        return (Self { moves }, sender);
+    }
+}
+
+impl Agent for GuiHuman {
+    fn name(&self) -> String {
+        return "Human".to_string();
+    }
+
+    fn select_move(&mut self, game_state: &GameState, _time_limit: Option<Duration>) -> Result<Turn, &'static str> {
+        loop {
+            let (coordinates, tile) = self.moves.recv().map_err(|_| "The window was closed")?;
+            if Empty.eq(&game_state.tile_at(coordinates)) {
+                return Ok(Turn::Move((coordinates, tile)));
+            }
+        }
+    }
+}
+
+/// Places a random pawn on a random empty cell, after an optional pause so that people can follow along.
+pub struct RandomAgent {
+    pub delay: Duration,
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub fn new(delay: Duration, seed: u64) -> Self {
+        Self { delay, rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> String {
+        return "Random".to_string();
+    }
+
+    fn select_move(&mut self, game_state: &GameState, _time_limit: Option<Duration>) -> Result<Turn, &'static str> {
+        thread::sleep(self.delay);
+        if !game_state.can_order_win() {
+            return Err("The board is full");
+        }
+        loop {
+            let coordinates = random_coordinates(&mut self.rng);
+            if Empty.eq(&game_state.tile_at(coordinates)) {
+                return Ok(Turn::Move((coordinates, random_pawn(&mut self.rng))));
+            }
+        }
+    }
+}
+
+/// The built-in alpha-beta engine, optionally following an opening book for the first moves.
+pub struct EngineAgent {
+    engine: Engine,
+    rng: StdRng,
+    book: Option<Book>,
+    book_plies: usize,
+}
+
+impl EngineAgent {
+    pub fn new(engine: Engine, seed: u64) -> Self {
+        Self { engine, rng: StdRng::seed_from_u64(seed), book: None, book_plies: 0 }
+    }
+
+    pub fn with_book(mut self, book: Book, plies: usize) -> Self {
+        self.book = Some(book);
+        self.book_plies = plies;
+        return self;
+    }
+}
+
+impl Agent for EngineAgent {
+    fn name(&self) -> String {
+        return format!("Engine ({})", self.engine.difficulty);
+    }
+
+    fn select_move(&mut self, game_state: &GameState, time_limit: Option<Duration>) -> Result<Turn, &'static str> {
+        if let Some(time_limit) = time_limit {
+            self.engine.time_limit = Some(time_limit);
+        }
+        if let Some(book) = &self.book {
+            if is_in_book_range(game_state, self.book_plies) {
+                if let Some(book_move) = book.lookup(game_state, &mut self.rng) {
+                    return Ok(Turn::Move(book_move));
+                }
+            }
+        }
+        return self.engine.best_move(game_state, &mut self.rng).map(Turn::Move).ok_or("The board is full");
+    }
+}
+
+/// A program in another process that speaks the engine protocol.
+pub struct ExternalAgent {
+    command: String,
+    engine: ExternalEngine,
+    move_time: Duration,
+}
+
+impl ExternalAgent {
+    pub fn start(command: &str, move_time: Duration) -> Result<Self, &'static str> {
+        let engine = ExternalEngine::start(command)?;
+        return Ok(Self { command: command.to_string(), engine, move_time });
+    }
+}
+
+impl Agent for ExternalAgent {
+    fn name(&self) -> String {
+        return self.command.clone();
+    }
+
+    fn select_move(&mut self, game_state: &GameState, time_limit: Option<Duration>) -> Result<Turn, &'static str> {
+        return self.engine.best_move(game_state, time_limit.unwrap_or(self.move_time)).map(Turn::Move);
+    }
+}
+
+/// Builds the computer agent the options ask for on the given side, if any: the demo's random mover, an external engine or the built-in engine.
+pub fn computer_agent(options: &Options, player: Player) -> Option<Box<dyn Agent>> {
+    if options.ai_vs_ai_demo {
+        return Some(Box::new(RandomAgent::new(Duration::from_secs(3), options.agent_seed(player))));
+    }
+    let renderer = Renderer::from_options(options);
+    if let Some(command) = options.external_engine(player) {
+        match ExternalAgent::start(command, Duration::from_millis(options.move_time)) {
+            Ok(agent) => return Some(Box::new(agent)),
+            Err(v) => show_error_message(&renderer, &options.language.format("Could not start \"{}\". Message: {}", &[command, &options.language.text(v)]))
+        }
+    }
+    if options.computer == Some(player) {
+        let mut agent = EngineAgent::new(Engine::new(options.difficulty), options.agent_seed(player));
+        if let Some(path) = &options.book {
+            match Book::load(path) {
+                Ok(book) => agent = agent.with_book(book, options.book_plies),
+                Err(v) => show_error_message(&renderer, v)
+            }
+        }
+        return Some(Box::new(agent));
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod agent_test {
+    use std::time::Duration;
+    use crate::agent::{Agent, EngineAgent, GuiHuman, PendingMove, RandomAgent, Turn};
+    use crate::engine::{Difficulty, Engine};
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_random_agent_plays_legal_moves() {
+        let mut agent = RandomAgent::new(Duration::ZERO, 7);
+        let mut game_state = GameState::default_new();
+        while game_state.can_order_win() {
+            let Ok(Turn::Move((coordinates, tile))) = agent.select_move(&game_state, None) else { panic!() };
+            game_state.play(coordinates, tile).expect("");
+        }
+        assert!(agent.select_move(&game_state, None).is_err());
+    }
+
+    #[test]
+    fn test_gui_human_skips_occupied_cells() {
+        let (agent, sender) = GuiHuman::new();
+        let mut game_state = GameState::default_new();
+        game_state.play((0, 0), Red).expect("");
+        let pending = PendingMove::start(Box::new(agent), game_state, None);
+        sender.send(((0, 0), Blue)).expect("");
+        sender.send(((1, 1), Blue)).expect("");
+        let (agent, result) = pending.finish();
+        assert!(agent.is_some());
+        assert_eq!(result, Ok(Turn::Move(((1, 1), Blue))));
+    }
+
+    #[test]
+    fn test_engine_agent_respects_time_limit() {
+        let mut agent = EngineAgent::new(Engine::new(Difficulty::Expert), 7);
+        let game_state = GameState::default_new();
+        let time_limit = Duration::from_millis(100);
+        assert!(agent.select_move(&game_state, Some(time_limit)).is_ok());
+        // The search stops at the deadline it derives from this limit.
+        assert_eq!(agent.engine.time_limit, Some(time_limit));
+        assert_eq!(agent.name(), "Engine (expert)");
+    }
+
+    #[test]
+    fn test_seeded_agents_repeat_their_games() {
+        let play_a_game = |seed: u64| {
+            let mut order = RandomAgent::new(Duration::ZERO, seed);
+            let mut chaos = RandomAgent::new(Duration::ZERO, seed + 1);
+            let mut game_state = GameState::default_new();
+            let mut moves = Vec::new();
+            while game_state.can_order_win() && !game_state.is_in_order() {
+                let agent = if moves.len() % 2 == 0 { &mut order } else { &mut chaos };
+                let Ok(Turn::Move((coordinates, tile))) = agent.select_move(&game_state, None) else { panic!() };
+                game_state.play(coordinates, tile).expect("");
+                moves.push((coordinates, tile));
+            }
+            moves
+        };
+        assert_eq!(play_a_game(1234), play_a_game(1234));
+        assert_ne!(play_a_game(1234), play_a_game(4321));
+    }
+}
@@ -0,0 +1,238 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::time::Duration;
+use crate::engine::{Difficulty, Engine};
+use crate::evaluation::WIN_SCORE;
+use crate::locale::Language;
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::record::GameRecord;
+use crate::state::{GameState, Move};
+
+/// How much worse than the best move, in evaluation points, a move has to be to count as a blunder.
+const BLUNDER_LOSS: i32 = 100;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Annotation {
+    /// Order could have completed five in a row but played something else.
+    MissedWin,
+    /// Chaos could have stopped every threat but left one open.
+    MissedSave,
+    Blunder,
+}
+
+impl Annotation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Annotation::MissedWin => "Missed win",
+            Annotation::MissedSave => "Missed save",
+            Annotation::Blunder => "Blunder"
+        }
+    }
+}
+
+/// The verdict on one move. Scores are from Order's point of view, as everywhere else.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveReview {
+    pub player: Player,
+    pub played_move: Move,
+    pub score: i32,
+    pub best_move: Move,
+    pub best_score: i32,
+    pub annotation: Option<Annotation>,
+}
+
+impl MoveReview {
+    /// How many points the mover gave away compared with the best move.
+    pub fn loss(&self) -> i32 {
+        match self.player {
+            Order => return self.best_score - self.score,
+            Chaos => return self.score - self.best_score
+        }
+    }
+}
+
+pub struct Analysis {
+    pub reviews: Vec<MoveReview>,
+    /// The index of the move after which the evaluation never left the winner's side again.
+    pub turning_point: Option<usize>,
+}
+
+impl Analysis {
+    pub fn count(&self, player: Player, annotation: Annotation) -> usize {
+        return self.reviews.iter().filter(|review| review.player == player && review.annotation == Some(annotation)).count();
+    }
+
+    /// The report in the given language. Files are always written in English.
+    pub fn to_text(&self, record: &GameRecord, language: Language) -> String {
+        let mut text = format!("{}\n", language.text("Analysis"));
+        for (name, value) in &record.tags {
+            text.push_str(&format!("{}: {}\n", name, value));
+        }
+        text.push('\n');
+        let width = [Order, Chaos].iter().map(|player| language.player(player).chars().count()).max().unwrap_or(5);
+        for (index, review) in self.reviews.iter().enumerate() {
+            text.push_str(&format!("{:>3}. {:<width$} {:<5} {:>+6}", index + 1, language.player(&review.player), language.move_text(&review.played_move), review.score, width = width));
+            if let Some(annotation) = review.annotation {
+                text.push_str("  ");
+                text.push_str(&language.format("{}, best was {}", &[&language.text(annotation.name()), &language.move_text(&review.best_move)]));
+                text.push_str(&format!(" ({:+})", review.best_score));
+            }
+            text.push('\n');
+        }
+        text.push('\n');
+        match self.turning_point {
+            Some(index) => {
+                let review = &self.reviews[index];
+                text.push_str(&language.format("Turning point: move {}, {} {}", &[&(index + 1), &language.player(&review.player), &language.move_text(&review.played_move)]));
+            }
+            None => text.push_str(language.text("Turning point: none"))
+        }
+        text.push('\n');
+        text.push_str(&language.format("{}: {} blunders, {} missed wins", &[&language.player(&Order), &self.count(Order, Annotation::Blunder), &self.count(Order, Annotation::MissedWin)]));
+        text.push('\n');
+        text.push_str(&language.format("{}: {} blunders, {} missed saves", &[&language.player(&Chaos), &self.count(Chaos, Annotation::Blunder), &self.count(Chaos, Annotation::MissedSave)]));
+        text.push('\n');
+        return text;
+    }
+
+    pub fn save(&self, record: &GameRecord, path: &str) -> Result<(), &'static str> {
+        return fs::write(path, self.to_text(record, Language::English)).map_err(|_| "Could not write the analysis");
+    }
+}
+
+/// Replays the record and lets the engine judge every move, thinking for `time_per_move` on each position.
+pub fn analyse_game(record: &GameRecord, time_per_move: Duration) -> Result<Analysis, &'static str> {
+    let mut engine = Engine::new(Difficulty::Expert);
+    engine.time_limit = Some(time_per_move);
+    let mut reviews = Vec::new();
+    for ply in 0..record.moves.len() {
+        let game_state = record.state_after(ply)?;
+        let played_move = record.moves[ply];
+        let scored_moves = engine.scored_moves(&game_state);
+        let score = scored_moves.iter().find(|(candidate, _)| *candidate == played_move).map(|(_, score)| *score).ok_or("The record contains an illegal move")?;
+        let (best_move, best_score) = match game_state.turn_player {
+            Order => scored_moves.iter().max_by_key(|(_, score)| *score),
+            Chaos => scored_moves.iter().min_by_key(|(_, score)| *score)
+        }.copied().unwrap();
+
+        let threats = game_state.threats();
+        let annotation = match game_state.turn_player {
+            Order if !threats.winning_moves.is_empty() && !threats.winning_moves.contains(&played_move) => Some(Annotation::MissedWin),
+            Chaos if !threats.saving_moves.is_empty() && !threats.saving_moves.contains(&played_move) => Some(Annotation::MissedSave),
+            _ => None
+        };
+        let mut review = MoveReview {
+            player: game_state.turn_player,
+            played_move,
+            score: clamp_score(score),
+            best_move,
+            best_score: clamp_score(best_score),
+            annotation,
+        };
+        if review.annotation.is_none() && review.loss() >= BLUNDER_LOSS {
+            review.annotation = Some(Annotation::Blunder);
+        }
+        reviews.push(review);
+    }
+
+    let winner = match record.tag("Result") {
+        Some("Order") => Some(Order),
+        Some("Chaos") => Some(Chaos),
+        _ => None
+    };
+    let turning_point = winner.and_then(|winner| turning_point(&reviews, winner));
+    return Ok(Analysis { reviews, turning_point });
+}
+
+/// The `count` moves the engine likes best for the turn player, best first, with their scores from Order's point of view.
+pub fn best_moves(game_state: &GameState, time_limit: Duration, count: usize) -> Vec<(Move, i32)> {
+    let mut engine = Engine::new(Difficulty::Expert);
+    engine.time_limit = Some(time_limit);
+    let mut scored_moves = engine.scored_moves(game_state);
+    scored_moves.sort_by_key(|(_, score)| match game_state.turn_player {
+        Order => -*score,
+        Chaos => *score
+    });
+    scored_moves.truncate(count);
+    return scored_moves.into_iter().map(|(candidate, score)| (candidate, clamp_score(score))).collect();
+}
+
+/// Search scores count faster wins as larger; for reports every win is worth the same.
+fn clamp_score(score: i32) -> i32 {
+    return score.clamp(-WIN_SCORE, WIN_SCORE);
+}
+
+fn turning_point(reviews: &[MoveReview], winner: Player) -> Option<usize> {
+    let favours_winner = |review: &MoveReview| match winner {
+        Order => review.score > 0,
+        Chaos => review.score < 0
+    };
+    let mut turning_point = None;
+    for (index, review) in reviews.iter().enumerate().rev() {
+        if !favours_winner(review) {
+            break;
+        }
+        turning_point = Some(index);
+    }
+    return turning_point;
+}
+
+#[cfg(test)]
+mod analysis_test {
+    use std::time::Duration;
+    use crate::analysis::{analyse_game, best_moves, Annotation};
+    use crate::locale::Language;
+    use crate::notation::parse_board;
+    use crate::players::Player::{Chaos, Order};
+    use crate::record::GameRecord;
+    use crate::tile::Tile::{Blue, Red};
+
+    fn record_with_missed_chances() -> GameRecord {
+        let mut record = GameRecord::new("A", "B", 1);
+        record.moves = vec![
+            ((0, 0), Red), ((5, 5), Blue),
+            ((0, 1), Red), ((5, 3), Blue),
+            ((0, 2), Red), ((4, 5), Blue),
+            ((0, 3), Red), ((3, 3), Blue),
+            ((3, 0), Red), ((0, 4), Blue),
+        ];
+        record.set_winner(Chaos);
+        return record;
+    }
+
+    #[test]
+    fn test_missed_wins_and_saves_are_flagged() {
+        let analysis = analyse_game(&record_with_missed_chances(), Duration::from_millis(20)).expect("");
+        assert_eq!(analysis.reviews.len(), 10);
+        assert_eq!(analysis.reviews[7].annotation, Some(Annotation::MissedSave));
+        assert_eq!(analysis.reviews[8].annotation, Some(Annotation::MissedWin));
+        assert_eq!(analysis.reviews[8].best_move, ((0, 4), Red));
+        assert_eq!(analysis.count(Order, Annotation::MissedWin), 1);
+        assert_eq!(analysis.count(Chaos, Annotation::MissedSave), 1);
+    }
+
+    #[test]
+    fn test_report_lists_every_move() {
+        let record = record_with_missed_chances();
+        let analysis = analyse_game(&record, Duration::from_millis(20)).expect("");
+        let text = analysis.to_text(&record, Language::English);
+        assert!(text.contains("Missed win, best was X E1"));
+        assert!(text.contains(" 10. Chaos O E1 "));
+        assert!(text.contains("Chaos: 0 blunders, 1 missed saves"));
+        let text = analysis.to_text(&record, Language::German);
+        assert!(text.contains("Verpasster Sieg, am besten war X E1"));
+        assert!(text.contains(" 10. Chaos   O E1 "));
+        assert!(text.contains("Chaos: 0 Patzer, 1 verpasste Rettungen"));
+    }
+
+    #[test]
+    fn test_best_moves_for_a_board() {
+        let game_state = parse_board("XXXX../....../....../....../....../....OO").expect("");
+        let moves = best_moves(&game_state, Duration::from_millis(20), 3);
+        assert_eq!(moves.len(), 3);
+        assert_eq!(moves[0].0, ((0, 4), Red));
+        assert!(moves[0].1 >= moves[1].1 && moves[1].1 >= moves[2].1);
+    }
+}
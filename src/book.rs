@@ -0,0 +1,312 @@
+#![allow(clippy::needless_return)]
+
+use std::collections::HashMap;
+use std::fs;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::engine::{Difficulty, Engine};
+use crate::notation::{board_to_notation, move_fmt, parse_move};
+use crate::players::Player::{Chaos, Order};
+use crate::record::GameRecord;
+use crate::state::{GameState, Move, BOARD_SIZE};
+use crate::tile::Tile;
+use crate::tile::Tile::{Blue, Red};
+
+/// One of the sixteen ways to turn or mirror the board and swap the pawns without changing the game.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Symmetry {
+    rotations: usize,
+    mirrored: bool,
+    swapped: bool,
+}
+
+impl Symmetry {
+    fn all() -> Vec<Symmetry> {
+        let mut symmetries = Vec::new();
+        for rotations in 0..4 {
+            for mirrored in [false, true] {
+                for swapped in [false, true] {
+                    symmetries.push(Symmetry { rotations, mirrored, swapped });
+                }
+            }
+        }
+        return symmetries;
+    }
+
+    fn cell(&self, coordinates: (usize, usize)) -> (usize, usize) {
+        let last = BOARD_SIZE - 1;
+        let (mut row, mut column) = coordinates;
+        if self.mirrored {
+            column = last - column;
+        }
+        for _ in 0..self.rotations {
+            (row, column) = (column, last - row);
+        }
+        return (row, column);
+    }
+
+    fn inverse_cell(&self, coordinates: (usize, usize)) -> (usize, usize) {
+        let last = BOARD_SIZE - 1;
+        let (mut row, mut column) = coordinates;
+        for _ in 0..self.rotations {
+            (row, column) = (last - column, row);
+        }
+        if self.mirrored {
+            column = last - column;
+        }
+        return (row, column);
+    }
+
+    fn tile(&self, tile: Tile) -> Tile {
+        match (tile, self.swapped) {
+            (Red, true) => Blue,
+            (Blue, true) => Red,
+            (tile, _) => tile
+        }
+    }
+
+    fn board(&self, game_state: &GameState) -> GameState {
+        let mut transformed = *game_state;
+        for row in 0..BOARD_SIZE {
+            for column in 0..BOARD_SIZE {
+                let (new_row, new_column) = self.cell((row, column));
+                transformed.board[new_row][new_column] = self.tile(game_state.board[row][column]);
+            }
+        }
+        return transformed;
+    }
+}
+
+/// The key shared by a position and all its rotations, reflections and colour swaps, with the symmetry that leads to it.
+fn canonical(game_state: &GameState) -> (String, Symmetry) {
+    let mut best: Option<(String, Symmetry)> = None;
+    for symmetry in Symmetry::all() {
+        let key = board_to_notation(&symmetry.board(game_state));
+        if best.as_ref().is_none_or(|(best_key, _)| key < *best_key) {
+            best = Some((key, symmetry));
+        }
+    }
+    return best.unwrap();
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookMove {
+    pub played_move: Move,
+    pub weight: u32,
+    pub games: u32,
+    pub order_wins: u32,
+}
+
+/// Recommended moves for early positions, keyed by canonical position so that equivalent openings share their statistics.
+///
+/// The file has one line per position and move: `<board> <pawn> <cell> <weight> <games> <order wins>`.
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+    entries: HashMap<String, Vec<BookMove>>,
+}
+
+impl Book {
+    pub fn len(&self) -> usize {
+        return self.entries.values().map(|moves| moves.len()).sum();
+    }
+
+    pub fn moves(&self, game_state: &GameState) -> Vec<BookMove> {
+        let (key, symmetry) = canonical(game_state);
+        let mut moves = self.entries.get(&key).cloned().unwrap_or_default();
+        for book_move in moves.iter_mut() {
+            let (coordinates, tile) = book_move.played_move;
+            book_move.played_move = (symmetry.inverse_cell(coordinates), symmetry.tile(tile));
+        }
+        return moves;
+    }
+
+    /// Picks one of the book moves for the position, favouring those with larger weights.
+    pub fn lookup<R: Rng>(&self, game_state: &GameState, rng: &mut R) -> Option<Move> {
+        let moves = self.moves(game_state);
+        let total: u32 = moves.iter().map(|book_move| book_move.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.gen_range(0..total);
+        for book_move in moves {
+            if pick < book_move.weight {
+                return Some(book_move.played_move);
+            }
+            pick -= book_move.weight;
+        }
+        return None;
+    }
+
+    /// Counts the first `plies` moves of a finished game.
+    pub fn add_game(&mut self, record: &GameRecord, plies: usize) -> Result<(), &'static str> {
+        let order_won = match record.tag("Result") {
+            Some("Order") => true,
+            Some("Chaos") => false,
+            _ => return Err("Only finished games can be added to the book")
+        };
+        if record.tag("Position").is_some() {
+            return Err("Games from a set-up position cannot be added to the book");
+        }
+        // Replay the opening first, so that a refused game leaves the book as it was.
+        let mut game_state = GameState::default_new();
+        for (coordinates, tile) in record.moves.iter().take(plies) {
+            game_state.play(*coordinates, *tile)?;
+        }
+        let mut game_state = GameState::default_new();
+        for (coordinates, tile) in record.moves.iter().take(plies) {
+            let (key, symmetry) = canonical(&game_state);
+            let played_move = (symmetry.cell(*coordinates), symmetry.tile(*tile));
+            let moves = self.entries.entry(key).or_default();
+            let index = match moves.iter().position(|book_move| book_move.played_move == played_move) {
+                Some(index) => index,
+                None => {
+                    moves.push(BookMove { played_move, weight: 0, games: 0, order_wins: 0 });
+                    moves.len() - 1
+                }
+            };
+            let mover_won = order_won == Order.eq(&game_state.turn_player);
+            let book_move = &mut moves[index];
+            book_move.games += 1;
+            if order_won {
+                book_move.order_wins += 1;
+            }
+            book_move.weight += if mover_won { 2 } else { 1 };
+            let _ = game_state.play(*coordinates, *tile);
+        }
+        return Ok(());
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut keys: Vec<&String> = self.entries.keys().collect();
+        keys.sort();
+        let mut text = String::new();
+        for key in keys {
+            for book_move in &self.entries[key] {
+                text.push_str(&format!("{} {} {} {} {}\n", key, move_fmt(&book_move.played_move), book_move.weight, book_move.games, book_move.order_wins));
+            }
+        }
+        return text;
+    }
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut book = Self::default();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() || words[0].starts_with('#') {
+                continue;
+            }
+            if words.len() != 6 {
+                return Err("A book line needs a board, a move, a weight, a game count and Order's wins");
+            }
+            let played_move = parse_move(&format!("{} {}", words[1], words[2]))?;
+            let numbers: Result<Vec<u32>, _> = words[3..].iter().map(|word| word.parse::<u32>()).collect();
+            let numbers = numbers.map_err(|_| "Book statistics must be whole numbers")?;
+            book.entries.entry(words[0].to_string()).or_default().push(BookMove {
+                played_move,
+                weight: numbers[0],
+                games: numbers[1],
+                order_wins: numbers[2],
+            });
+        }
+        return Ok(book);
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), &'static str> {
+        return fs::write(path, self.to_text()).map_err(|_| "Could not write the opening book");
+    }
+
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        let text = fs::read_to_string(path).map_err(|_| "Could not read the opening book")?;
+        return Self::parse(&text);
+    }
+}
+
+/// Plays `games` engine games against itself and records the results. The engine searches to the difficulty's depth
+/// without a time limit, so the same seed always plays the same games.
+pub fn self_play(games: usize, difficulty: Difficulty, seed: u64) -> Vec<GameRecord> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut engine = Engine::new(difficulty);
+    engine.time_limit = None;
+    let mut records = Vec::new();
+    for _ in 0..games {
+        let mut record = GameRecord::new("Self-play", "Self-play", seed);
+        let mut game_state = GameState::default_new();
+        while game_state.can_order_win() && !game_state.is_in_order() {
+            let (coordinates, tile) = match engine.best_move(&game_state, &mut rng) {
+                Some(v) => v,
+                None => break
+            };
+            let _ = game_state.play(coordinates, tile);
+            record.moves.push((coordinates, tile));
+        }
+        record.set_winner(if game_state.is_in_order() { Order } else { Chaos });
+        records.push(record);
+    }
+    return records;
+}
+
+/// Adds `games` new self-play games to the book. They always finish and start from the empty board, so none is refused.
+pub fn add_self_play(book: &mut Book, games: usize, plies: usize, difficulty: Difficulty, seed: u64) {
+    for record in self_play(games, difficulty, seed) {
+        let _ = book.add_game(&record, plies);
+    }
+}
+
+/// Whether the position is still within the first `plies` moves of the game.
+pub fn is_in_book_range(game_state: &GameState, plies: usize) -> bool {
+    let pieces = BOARD_SIZE * BOARD_SIZE - game_state.empty_cells().len();
+    return pieces < plies;
+}
+
+#[cfg(test)]
+mod book_test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::book::{canonical, Book, Symmetry};
+    use crate::players::Player::Order;
+    use crate::record::GameRecord;
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_symmetries_are_reversible() {
+        for symmetry in Symmetry::all() {
+            for row in 0..6 {
+                for column in 0..6 {
+                    assert_eq!(symmetry.inverse_cell(symmetry.cell((row, column))), (row, column));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_equivalent_positions_share_a_key() {
+        let mut corner = GameState::default_new();
+        corner.play((0, 0), Red).expect("");
+        let mut other_corner = GameState::default_new();
+        other_corner.play((5, 0), Blue).expect("");
+        assert_eq!(canonical(&corner).0, canonical(&other_corner).0);
+    }
+
+    #[test]
+    fn test_book_moves_follow_the_position() {
+        let mut record = GameRecord::new("A", "B", 1);
+        record.moves.push(((0, 0), Red));
+        record.moves.push(((0, 1), Blue));
+        record.set_winner(Order);
+        let mut book = Book::default();
+        book.add_game(&record, 2).expect("");
+
+        let mut mirrored = GameState::default_new();
+        mirrored.play((0, 5), Red).expect("");
+        let moves = book.moves(&mirrored);
+        assert_eq!(moves.len(), 1);
+        assert!(moves[0].played_move == ((0, 4), Blue) || moves[0].played_move == ((1, 5), Blue));
+        assert_eq!(book.lookup(&mirrored, &mut StdRng::seed_from_u64(3)), Some(moves[0].played_move));
+
+        let parsed = Book::parse(&book.to_text()).expect("");
+        assert_eq!(parsed.moves(&mirrored), moves);
+        assert_eq!(parsed.len(), 2);
+    }
+}
@@ -0,0 +1,544 @@
+#![allow(clippy::needless_return)]
+
+use std::str::FromStr;
+use structopt::StructOpt;
+use crate::clock::TimeControl;
+use crate::config::{parse_player, Options};
+use crate::diagram::{parse_arrow, Arrow};
+use crate::engine::Difficulty;
+use crate::players::Player;
+
+#[derive(StructOpt, Debug, Clone)]
+#[structopt(name = "Order & Chaos", about = "An emulator for the classic two-player board game \"Order and Chaos\"")]
+pub struct Cli {
+    #[structopt(long, global = true, help = "Reads default settings from the given TOML file instead of config.toml in the user's configuration directory.")]
+    pub config: Option<String>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub enum Command {
+    #[structopt(about = "Plays a game in the window, or in the terminal with --mode terminal. This is what runs without a command.")]
+    Play {
+        #[structopt(flatten)]
+        window: WindowOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+        #[structopt(flatten)]
+        players: PlayerOptions,
+        #[structopt(flatten)]
+        game: GameOptions,
+        #[structopt(flatten)]
+        analysis: AnalysisOptions,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Shows a demo of the game with random moves.")]
+    Demo {
+        #[structopt(flatten)]
+        window: WindowOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+        #[structopt(flatten)]
+        game: GameOptions,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Opens a saved game record to step through it move by move.")]
+    Replay {
+        #[structopt(help = "The saved game record.")]
+        file: String,
+        #[structopt(long, default_value = "1000", help = "Milliseconds between moves when a replay plays on its own.")]
+        replay_speed: u64,
+        #[structopt(flatten)]
+        window: WindowOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+    },
+
+    #[structopt(about = "Analyses a saved game record, or ranks the moves of a board, prints the report and exits.")]
+    Analyse {
+        #[structopt(help = "A saved game record, or a board such as X...../....../....../....../....../......")]
+        input: String,
+        #[structopt(flatten)]
+        analysis: AnalysisOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+    },
+
+    #[structopt(about = "Searches a board, or the final position of a saved game, for a forced win by Order.")]
+    Solve {
+        #[structopt(help = "A board such as X...../....../....../....../....../...... or a saved game record.")]
+        input: String,
+        #[structopt(long, default_value = "2", help = "Number of Order moves to search ahead.")]
+        depth: usize,
+        #[structopt(flatten)]
+        style: StyleOptions,
+    },
+
+    #[structopt(about = "Plays a match of game pairs, with the players swapping sides after every game.")]
+    Tournament {
+        #[structopt(long, default_value = "1", help = "Number of game pairs to play.")]
+        pairs: usize,
+        #[structopt(long, help = "Saves the games of the match to the given file after every game. An unfinished match in the file is resumed.")]
+        match_file: Option<String>,
+        #[structopt(flatten)]
+        window: WindowOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+        #[structopt(flatten)]
+        players: PlayerOptions,
+        #[structopt(flatten)]
+        game: GameOptions,
+        #[structopt(flatten)]
+        analysis: AnalysisOptions,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Hosts a game on the given TCP port and waits for another player to connect. The hosted game runs in the terminal.")]
+    Serve {
+        #[structopt(help = "The TCP port to listen on.")]
+        port: u16,
+        #[structopt(long, default_value = "127.0.0.1", help = "The address to listen on. Only this computer can join unless it is set to another address, e.g. 0.0.0.0 for every network.")]
+        bind: String,
+        #[structopt(long, default_value = "order", parse(try_from_str = parse_player), help = "The side the host plays.")]
+        side: Player,
+        #[structopt(flatten)]
+        style: StyleOptions,
+        #[structopt(flatten)]
+        players: PlayerOptions,
+        #[structopt(flatten)]
+        game: GameOptions,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Joins a game hosted with the serve command.")]
+    Connect {
+        #[structopt(help = "The host and port, e.g. 192.168.1.20:7777.")]
+        address: String,
+        #[structopt(flatten)]
+        window: WindowOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+        #[structopt(flatten)]
+        players: PlayerOptions,
+        #[structopt(flatten)]
+        game: GameOptions,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Speaks the line-based engine protocol on stdin and stdout.")]
+    Engine {
+        #[structopt(long, default_value = "intermediate", help = "Playing strength of the engine until the protocol changes it.")]
+        difficulty: Difficulty,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Serves the HTTP/JSON game API on the given port.")]
+    Http {
+        #[structopt(help = "The TCP port to listen on.")]
+        port: u16,
+        #[structopt(long, default_value = "127.0.0.1", help = "The address to listen on. Only this computer can reach the API unless it is set to another address, e.g. 0.0.0.0 for every network.")]
+        bind: String,
+    },
+
+    #[structopt(about = "Adds one move to a correspondence game file and writes it back for the opponent. A new game is started if the file does not exist.")]
+    Correspondence {
+        #[structopt(help = "The game file.")]
+        file: String,
+        #[structopt(long, default_value = "order", parse(try_from_str = parse_player), help = "The side you play.")]
+        side: Player,
+        #[structopt(long, env = "ORDER_AND_CHAOS_SECRET", hide_env_values = true, help = "A passphrase both players agree on. The file is signed with it, so that only they can change it.")]
+        secret: String,
+        #[structopt(flatten)]
+        style: StyleOptions,
+        #[structopt(flatten)]
+        players: PlayerOptions,
+    },
+
+    #[structopt(about = "Trains with the puzzles in the given file. Results are saved back to the file.")]
+    Puzzles {
+        #[structopt(help = "The puzzle file.")]
+        file: String,
+        #[structopt(flatten)]
+        window: WindowOptions,
+        #[structopt(flatten)]
+        style: StyleOptions,
+    },
+
+    #[structopt(about = "Finds puzzles in self-play games and writes them to the given file.")]
+    GeneratePuzzles {
+        #[structopt(help = "The puzzle file to write.")]
+        file: String,
+        #[structopt(long, default_value = "20", help = "Number of puzzles to generate.")]
+        count: usize,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Builds an opening book from self-play and saved games and writes it to the given file.")]
+    Book {
+        #[structopt(help = "The book file to write.")]
+        file: String,
+        #[structopt(long, default_value = "50", help = "Number of self-play games.")]
+        games: usize,
+        #[structopt(long, help = "Saved game records to add to the book.")]
+        from: Vec<String>,
+        #[structopt(long, default_value = "6", help = "Number of moves at the start of each game that are added to the book.")]
+        plies: usize,
+        #[structopt(long, default_value = "intermediate", help = "Playing strength of the self-play games.")]
+        difficulty: Difficulty,
+        #[structopt(flatten)]
+        seed: SeedOptions,
+    },
+
+    #[structopt(about = "Draws a diagram of a board or of the final position of a saved game.")]
+    Diagram {
+        #[structopt(help = "A board such as X...../....../....../....../....../...... or a saved game record.")]
+        input: String,
+        #[structopt(short, long, default_value = "diagram.svg", help = "The picture to write, as SVG or, if the name ends in .png, as PNG.")]
+        output: String,
+        #[structopt(long = "arrow", parse(try_from_str = parse_arrow), help = "Adds an arrow such as A1-C3. May be repeated.")]
+        arrows: Vec<Arrow>,
+        #[structopt(long, help = "Leaves the coordinates off the diagram.")]
+        no_labels: bool,
+        #[structopt(long, default_value = "64", help = "Width of one cell in pixels.")]
+        cell_size: u32,
+    },
+}
+
+/// Where a game is shown: in the window or as text in the terminal.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Mode {
+    Gui,
+    Terminal,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gui" => return Ok(Mode::Gui),
+            "terminal" => return Ok(Mode::Terminal),
+            _ => return Err(format!("Unknown mode \"{}\". Expected gui or terminal", name))
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct WindowOptions {
+    #[structopt(long, conflicts_with = "terminal", help = "Where the game is shown: gui for the window or terminal for a text console.")]
+    pub mode: Option<Mode>,
+
+    #[structopt(short = "t", long, help = "Disables The Graphical User Interface and relies on a terminal console. Short for --mode terminal.")]
+    pub terminal: bool,
+
+    #[structopt(long, default_value = "110", help = "Size of a board cell in the window, in points.")]
+    pub tile_size: f32,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct StyleOptions {
+    #[structopt(long, help = "Disables Emoji output in the terminal.")]
+    pub disable_emoji: bool,
+
+    #[structopt(long, help = "Disables Emoji and Colored text output in the terminal.")]
+    pub disable_color_and_emoji: bool,
+
+    #[structopt(long, help = "Uses Emoji and Colored text output even when the terminal does not seem to support them, or when the output is not a terminal.")]
+    pub force_color: bool,
+
+    #[structopt(long, default_value = "default", help = "Colours of the board and players: default, high-contrast, colour-blind-safe, monochrome or a theme from the configuration file.")]
+    pub theme: String,
+
+    #[structopt(long, help = "Language of the game: en or de. Taken from the environment, e.g. LANG, if it is not set.")]
+    pub language: Option<String>,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct PlayerOptions {
+    #[structopt(long, parse(try_from_str = parse_player), help = "Lets the computer play the given side (order or chaos).")]
+    pub computer: Option<Player>,
+
+    #[structopt(long, default_value = "intermediate", help = "Playing strength of the computer: beginner, casual, intermediate, advanced or expert.")]
+    pub difficulty: Difficulty,
+
+    #[structopt(long, help = "Command that starts an external engine to play Order.")]
+    pub order_engine: Option<String>,
+
+    #[structopt(long, help = "Command that starts an external engine to play Chaos.")]
+    pub chaos_engine: Option<String>,
+
+    #[structopt(long, default_value = "1000", help = "Milliseconds an external engine may think about each move.")]
+    pub move_time: u64,
+
+    #[structopt(long, help = "Opening book the computer plays from during the first moves of a game.")]
+    pub book: Option<String>,
+
+    #[structopt(long, default_value = "6", help = "Number of moves at the start of a game that are looked up in the opening book.")]
+    pub book_plies: usize,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct GameOptions {
+    #[structopt(long, help = "Chess clock for both sides in seconds: <base>[+<increment>][/<period>x<periods>], e.g. 300+5 or 600/30x3.")]
+    pub time_control: Option<TimeControl>,
+
+    #[structopt(long, help = "Saves the record of the game to the given file once it is over.")]
+    pub save: Option<String>,
+
+    #[structopt(long, help = "Exports the game as JSON to the given file once it is over.")]
+    pub export_json: Option<String>,
+
+    #[structopt(long, help = "File that diagrams of the board are saved to, as SVG or, if the name ends in .png, as PNG.")]
+    pub diagram: Option<String>,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct AnalysisOptions {
+    #[structopt(long, help = "Analyses a game played in the terminal once it is over. Writing the analysis with --analysis-file implies it.")]
+    pub analyse: bool,
+
+    #[structopt(long, help = "Writes the analysis to the given file.")]
+    pub analysis_file: Option<String>,
+
+    #[structopt(long, default_value = "200", help = "Milliseconds the analysis spends on each move.")]
+    pub analysis_time: u64,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct SeedOptions {
+    #[structopt(long, help = "Seeds every random choice, so that a game can be replayed exactly. A random seed is chosen if it is not set.")]
+    pub seed: Option<u64>,
+
+    #[structopt(long, help = "Takes the seed from a saved game record, so that its random moves are played again.")]
+    pub seed_from: Option<String>,
+}
+
+impl WindowOptions {
+    fn apply_to(&self, options: &mut Options) {
+        options.disable_gui = self.terminal || self.mode == Some(Mode::Terminal);
+        options.tile_size = self.tile_size;
+    }
+}
+
+impl StyleOptions {
+    fn apply_to(&self, options: &mut Options) {
+        options.disable_emoji = self.disable_emoji;
+        options.disable_color_and_emoji = self.disable_color_and_emoji;
+        options.force_color = self.force_color;
+        options.theme_name = self.theme.clone();
+        options.language_name = self.language.clone();
+    }
+}
+
+impl PlayerOptions {
+    fn apply_to(&self, options: &mut Options) {
+        options.computer = self.computer;
+        options.difficulty = self.difficulty;
+        options.order_engine = self.order_engine.clone();
+        options.chaos_engine = self.chaos_engine.clone();
+        options.move_time = self.move_time;
+        options.book = self.book.clone();
+        options.book_plies = self.book_plies;
+    }
+}
+
+impl GameOptions {
+    fn apply_to(&self, options: &mut Options) {
+        options.time_control = self.time_control;
+        options.save = self.save.clone();
+        options.export_json = self.export_json.clone();
+        options.diagram = self.diagram.clone();
+    }
+}
+
+impl AnalysisOptions {
+    fn apply_to(&self, options: &mut Options) {
+        options.analyse = self.analyse || self.analysis_file.is_some();
+        options.analysis_file = self.analysis_file.clone();
+        options.analysis_time = self.analysis_time;
+    }
+}
+
+impl SeedOptions {
+    fn apply_to(&self, options: &mut Options) {
+        options.seed = self.seed;
+        options.seed_from = self.seed_from.clone();
+    }
+}
+
+impl Command {
+    /// The name clap knows the command by, to find its arguments among the matches.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Play { .. } => "play",
+            Command::Demo { .. } => "demo",
+            Command::Replay { .. } => "replay",
+            Command::Analyse { .. } => "analyse",
+            Command::Solve { .. } => "solve",
+            Command::Tournament { .. } => "tournament",
+            Command::Serve { .. } => "serve",
+            Command::Connect { .. } => "connect",
+            Command::Engine { .. } => "engine",
+            Command::Http { .. } => "http",
+            Command::Correspondence { .. } => "correspondence",
+            Command::Puzzles { .. } => "puzzles",
+            Command::GeneratePuzzles { .. } => "generate-puzzles",
+            Command::Book { .. } => "book",
+            Command::Diagram { .. } => "diagram"
+        }
+    }
+
+    /// The settings the rest of the program reads, with everything the command does not set left at its default.
+    pub fn options(&self) -> Options {
+        let mut options = Options::default_options();
+        match self {
+            Command::Play { window, style, players, game, analysis, seed } => {
+                window.apply_to(&mut options);
+                style.apply_to(&mut options);
+                players.apply_to(&mut options);
+                game.apply_to(&mut options);
+                analysis.apply_to(&mut options);
+                seed.apply_to(&mut options);
+            }
+            Command::Demo { window, style, game, seed } => {
+                options.ai_vs_ai_demo = true;
+                window.apply_to(&mut options);
+                style.apply_to(&mut options);
+                game.apply_to(&mut options);
+                seed.apply_to(&mut options);
+            }
+            Command::Replay { file, replay_speed, window, style } => {
+                options.replay = Some(file.clone());
+                options.replay_speed = *replay_speed;
+                window.apply_to(&mut options);
+                style.apply_to(&mut options);
+            }
+            Command::Analyse { analysis, style, .. } => {
+                analysis.apply_to(&mut options);
+                style.apply_to(&mut options);
+            }
+            Command::Solve { style, .. } => style.apply_to(&mut options),
+            Command::Tournament { pairs, match_file, window, style, players, game, analysis, seed } => {
+                options.match_pairs = Some(*pairs);
+                options.match_file = match_file.clone();
+                window.apply_to(&mut options);
+                style.apply_to(&mut options);
+                players.apply_to(&mut options);
+                game.apply_to(&mut options);
+                analysis.apply_to(&mut options);
+                seed.apply_to(&mut options);
+            }
+            Command::Serve { style, players, game, seed, .. } => {
+                options.disable_gui = true;
+                style.apply_to(&mut options);
+                players.apply_to(&mut options);
+                game.apply_to(&mut options);
+                seed.apply_to(&mut options);
+            }
+            Command::Connect { address, window, style, players, game, seed } => {
+                options.connect = Some(address.clone());
+                window.apply_to(&mut options);
+                style.apply_to(&mut options);
+                players.apply_to(&mut options);
+                game.apply_to(&mut options);
+                seed.apply_to(&mut options);
+            }
+            Command::Engine { difficulty, seed } => {
+                options.difficulty = *difficulty;
+                seed.apply_to(&mut options);
+            }
+            Command::Http { .. } => {}
+            Command::Correspondence { style, players, .. } => {
+                options.disable_gui = true;
+                style.apply_to(&mut options);
+                players.apply_to(&mut options);
+            }
+            Command::Puzzles { file, window, style } => {
+                options.puzzles = Some(file.clone());
+                window.apply_to(&mut options);
+                style.apply_to(&mut options);
+            }
+            Command::GeneratePuzzles { seed, .. } => seed.apply_to(&mut options),
+            Command::Book { plies, difficulty, seed, .. } => {
+                options.book_plies = *plies;
+                options.difficulty = *difficulty;
+                seed.apply_to(&mut options);
+            }
+            Command::Diagram { .. } => {}
+        }
+        return options;
+    }
+}
+
+impl Cli {
+    /// The command to run, which is a game in the window when none is given.
+    pub fn command(&self) -> Command {
+        return self.command.clone().unwrap_or_else(|| Command::from_iter(["order_and_chaos", "play"]));
+    }
+}
+
+#[cfg(test)]
+mod cli_test {
+    use structopt::StructOpt;
+    use crate::cli::{Cli, Command};
+    use crate::config::Options;
+    use crate::engine::Difficulty;
+    use crate::players::Player::Chaos;
+
+    fn parse(args: &[&str]) -> (Command, Options) {
+        let cli = Cli::from_iter(args);
+        let command = cli.command();
+        let options = command.options();
+        return (command, options);
+    }
+
+    #[test]
+    fn test_play_is_the_default() {
+        let (command, options) = parse(&["order_and_chaos"]);
+        assert_eq!(command.name(), "play");
+        assert!(!options.disable_gui);
+        assert!(!options.ai_vs_ai_demo);
+
+        let (_, options) = parse(&["order_and_chaos", "play", "-t", "--computer", "chaos", "--difficulty", "expert", "--seed", "7"]);
+        assert!(options.disable_gui);
+        assert_eq!(options.computer, Some(Chaos));
+        assert_eq!(options.difficulty, Difficulty::Expert);
+        assert_eq!(options.seed, Some(7));
+
+        let (_, options) = parse(&["order_and_chaos", "play", "--mode", "terminal"]);
+        assert!(options.disable_gui);
+        let (_, options) = parse(&["order_and_chaos", "play", "--mode", "gui"]);
+        assert!(!options.disable_gui);
+        assert!(Cli::from_iter_safe(["order_and_chaos", "play", "--mode", "tui"]).is_err());
+        assert!(Cli::from_iter_safe(["order_and_chaos", "play", "--mode", "gui", "--terminal"]).is_err());
+    }
+
+    #[test]
+    fn test_commands_set_their_own_options() {
+        let (_, options) = parse(&["order_and_chaos", "demo", "--terminal", "--disable-emoji"]);
+        assert!(options.ai_vs_ai_demo && options.disable_gui && options.disable_emoji);
+
+        let (_, options) = parse(&["order_and_chaos", "replay", "game.txt", "--replay-speed", "250"]);
+        assert_eq!(options.replay.as_deref(), Some("game.txt"));
+        assert_eq!(options.replay_speed, 250);
+
+        let (command, options) = parse(&["order_and_chaos", "tournament", "--pairs", "3", "--computer", "chaos"]);
+        assert_eq!(command.name(), "tournament");
+        assert_eq!(options.match_pairs, Some(3));
+
+        assert!(Cli::from_iter_safe(["order_and_chaos", "replay"]).is_err());
+        assert!(Cli::from_iter_safe(["order_and_chaos", "http", "80", "--computer", "chaos"]).is_err());
+        assert!(Cli::from_iter_safe(["order_and_chaos", "--demo"]).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+#![allow(clippy::needless_return)]
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+
+/// How much thinking time each side gets: a base time, an increment added after every move and, once the base time
+/// is used up, a number of byo-yomi periods in each of which a move has to be made.
+///
+/// Written as `<base>[+<increment>][/<period>x<periods>]` in seconds, e.g. `300+5` or `600/30x3`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+    pub byo_yomi: Duration,
+    pub periods: u32,
+}
+
+impl FromStr for TimeControl {
+    type Err = &'static str;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let seconds = |text: &str| text.trim().parse::<u64>().map(Duration::from_secs).map_err(|_| "Times must be whole numbers of seconds");
+        let (main, byo_yomi) = match text.split_once('/') {
+            Some((main, byo_yomi)) => (main, Some(byo_yomi)),
+            None => (text, None)
+        };
+        let (base, increment) = match main.split_once('+') {
+            Some((base, increment)) => (seconds(base)?, seconds(increment)?),
+            None => (seconds(main)?, Duration::ZERO)
+        };
+        let (byo_yomi, periods) = match byo_yomi {
+            Some(byo_yomi) => {
+                let (period, periods) = byo_yomi.split_once('x').unwrap_or((byo_yomi, "1"));
+                (seconds(period)?, periods.trim().parse::<u32>().map_err(|_| "The number of byo-yomi periods must be a whole number")?)
+            }
+            None => (Duration::ZERO, 0)
+        };
+        if base.is_zero() && (byo_yomi.is_zero() || periods == 0) {
+            return Err("A time control needs some time to play with");
+        }
+        return Ok(Self { base, increment, byo_yomi, periods });
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.base.as_secs())?;
+        if !self.increment.is_zero() {
+            write!(f, "+{}", self.increment.as_secs())?;
+        }
+        if self.periods > 0 {
+            write!(f, "/{}x{}", self.byo_yomi.as_secs(), self.periods)?;
+        }
+        return Ok(());
+    }
+}
+
+/// The time one side has left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlayerClock {
+    pub remaining: Duration,
+    pub periods: u32,
+}
+
+impl PlayerClock {
+    /// The clock after `elapsed` more thinking time, or `None` if that ran it out.
+    fn after(&self, elapsed: Duration, control: &TimeControl) -> Option<PlayerClock> {
+        if elapsed <= self.remaining {
+            return Some(PlayerClock { remaining: self.remaining - elapsed, periods: self.periods });
+        }
+        if control.byo_yomi.is_zero() {
+            return None;
+        }
+        let overtime = elapsed - self.remaining;
+        let used_periods = (overtime.as_millis() / control.byo_yomi.as_millis()) as u32;
+        if used_periods >= self.periods {
+            return None;
+        }
+        return Some(PlayerClock { remaining: Duration::ZERO, periods: self.periods - used_periods });
+    }
+
+    pub fn is_in_byo_yomi(&self) -> bool {
+        return self.remaining.is_zero() && self.periods > 0;
+    }
+}
+
+/// A chess clock for both sides. Only the turn player's clock runs.
+pub struct Clocks {
+    pub control: TimeControl,
+    order: PlayerClock,
+    chaos: PlayerClock,
+    running: Option<(Player, Instant)>,
+}
+
+impl Clocks {
+    pub fn new(control: TimeControl) -> Self {
+        let clock = PlayerClock { remaining: control.base, periods: control.periods };
+        return Self { control, order: clock, chaos: clock, running: None };
+    }
+
+    fn stored(&self, player: Player) -> PlayerClock {
+        match player {
+            Order => self.order,
+            Chaos => self.chaos
+        }
+    }
+
+    fn clock_mut(&mut self, player: Player) -> &mut PlayerClock {
+        match player {
+            Order => &mut self.order,
+            Chaos => &mut self.chaos
+        }
+    }
+
+    pub fn start(&mut self, player: Player) {
+        self.running = Some((player, Instant::now()));
+    }
+
+    /// Stops the running clock and charges the time the move took, or returns the player whose time ran out.
+    pub fn stop(&mut self) -> Result<(), Player> {
+        let (player, started) = match self.running.take() {
+            Some(v) => v,
+            None => return Ok(())
+        };
+        return self.charge(player, started.elapsed());
+    }
+
+    fn charge(&mut self, player: Player, elapsed: Duration) -> Result<(), Player> {
+        let control = self.control;
+        let clock = self.clock_mut(player);
+        match clock.after(elapsed, &control) {
+            Some(mut next) => {
+                if !next.is_in_byo_yomi() {
+                    next.remaining += control.increment;
+                }
+                *clock = next;
+                return Ok(());
+            }
+            None => {
+                *clock = PlayerClock { remaining: Duration::ZERO, periods: 0 };
+                return Err(player);
+            }
+        }
+    }
+
+    /// The clock of `player` as it stands right now, counting the time of a move in progress.
+    pub fn clock(&self, player: Player) -> Option<PlayerClock> {
+        let clock = self.stored(player);
+        match self.running {
+            Some((running, started)) if running == player => return clock.after(started.elapsed(), &self.control),
+            _ => return Some(clock)
+        }
+    }
+
+    /// The player whose time has run out during the current move, if any.
+    pub fn flagged(&self) -> Option<Player> {
+        let (player, _) = self.running?;
+        if self.clock(player).is_none() {
+            return Some(player);
+        }
+        return None;
+    }
+
+    /// How long a computer player may think about its next move without risking its clock.
+    pub fn move_budget(&self, player: Player) -> Duration {
+        let clock = match self.clock(player) {
+            Some(v) => v,
+            None => return Duration::ZERO
+        };
+        if clock.is_in_byo_yomi() {
+            return self.control.byo_yomi.mul_f64(0.8);
+        }
+        let budget = clock.remaining / 20 + self.control.increment.mul_f64(0.8);
+        return budget.min(clock.remaining.mul_f64(0.8));
+    }
+
+    pub fn clock_fmt(&self, player: Player) -> String {
+        match self.clock(player) {
+            None => return "0:00".to_string(),
+            Some(clock) if clock.is_in_byo_yomi() => {
+                let period_left = match self.running {
+                    Some((running, started)) if running == player => {
+                        let overtime = started.elapsed().saturating_sub(self.stored(player).remaining);
+                        let period = self.control.byo_yomi.as_millis().max(1);
+                        Duration::from_millis((period - overtime.as_millis() % period) as u64)
+                    }
+                    _ => self.control.byo_yomi
+                };
+                return format!("{}s ×{}", period_left.as_secs(), clock.periods);
+            }
+            Some(clock) => {
+                let seconds = clock.remaining.as_secs();
+                return format!("{}:{:02}", seconds / 60, seconds % 60);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod clock_test {
+    use std::time::Duration;
+    use crate::clock::{Clocks, TimeControl};
+    use crate::players::Player::{Chaos, Order};
+
+    #[test]
+    fn test_time_control_round_trip() {
+        for text in ["300", "300+5", "600/30x3", "0/10x1"] {
+            assert_eq!(text.parse::<TimeControl>().expect("").to_string(), text);
+        }
+        assert!("0".parse::<TimeControl>().is_err());
+        assert!("five minutes".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn test_increment_and_flag() {
+        let mut clocks = Clocks::new("10+2".parse().expect(""));
+        assert_eq!(clocks.charge(Order, Duration::from_secs(4)), Ok(()));
+        assert_eq!(clocks.clock(Order).expect("").remaining, Duration::from_secs(8));
+        assert_eq!(clocks.charge(Chaos, Duration::from_secs(11)), Err(Chaos));
+        assert_eq!(clocks.clock_fmt(Chaos), "0:00");
+    }
+
+    #[test]
+    fn test_byo_yomi_periods() {
+        let mut clocks = Clocks::new("10/5x2".parse().expect(""));
+        assert_eq!(clocks.charge(Order, Duration::from_secs(12)), Ok(()));
+        assert!(clocks.clock(Order).expect("").is_in_byo_yomi());
+        assert_eq!(clocks.clock(Order).expect("").periods, 2);
+        assert_eq!(clocks.charge(Order, Duration::from_secs(6)), Ok(()));
+        assert_eq!(clocks.clock(Order).expect("").periods, 1);
+        assert_eq!(clocks.charge(Order, Duration::from_secs(5)), Err(Order));
+        assert!(clocks.move_budget(Chaos) < Duration::from_secs(10));
+    }
+}
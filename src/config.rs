@@ -1,40 +1,174 @@
-use std::sync::{RwLock, RwLockReadGuard};
-use structopt::StructOpt;
-
-#[derive(StructOpt, Debug)]
-#[structopt(name = "Order & Chaos", about = "An emulator for the classic two-player board game \"Order and Chaos\"")]
-pub struct Options {
-    #[structopt(long = "demo", help = "Shows a demo of the game with random moves.")]
-    pub ai_vs_ai_demo: bool,
-
-    #[structopt(short = "t", long="terminal", help = "Disables The Graphical User Interface and relies on a terminal console.")]
-    pub disable_gui: bool,
-
-    #[structopt(long, help = "Disables Emoji output. Only applicable if --terminal is also set")]
-    pub disable_emoji: bool,
-
-    #[structopt(long, help = "Disables Emoji and Colored text output. Only applicable if --terminal is also set")]
-    pub disable_color_and_emoji: bool,
-}
-
-impl Options {
-    pub const fn default_options() -> Self {
-        Self {
-            ai_vs_ai_demo: false,
-            disable_emoji: false,
-            disable_color_and_emoji: false,
-            disable_gui: false,
-        }
-    }
-}
-
-static GLOBAL_OPTIONS: RwLock<Options> = RwLock::new(Options::default_options());
-
-pub fn read_options() {
-    let mut options_w = GLOBAL_OPTIONS.write().unwrap();
-    *options_w = Options::from_args();
-}
-
-pub fn get() -> RwLockReadGuard<'static, Options> {
-    return GLOBAL_OPTIONS.read().unwrap();
-}
\ No newline at end of file
+#![allow(clippy::needless_return)]
+
+use structopt::StructOpt;
+use crate::cli::{Cli, Command};
+use crate::clock::TimeControl;
+use crate::engine::Difficulty;
+use crate::locale::Language;
+use crate::preferences::{preferences_path, Preferences};
+use crate::record::GameRecord;
+use crate::theme::{Theme, BUILT_IN_THEMES};
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+
+/// Size of a board cell in the window, in points, unless the command or the configuration file sets another.
+pub const DEFAULT_TILE_SIZE: f32 = 110.0;
+
+/// Everything a run of the program is set up with, gathered from the command and the configuration file.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub ai_vs_ai_demo: bool,
+    pub disable_gui: bool,
+    pub disable_emoji: bool,
+    pub disable_color_and_emoji: bool,
+    pub force_color: bool,
+    /// The name of the theme, which is looked up once the configuration file is read.
+    pub theme_name: String,
+    pub theme: Theme,
+    /// The language asked for, which the environment decides if it is not set.
+    pub language_name: Option<String>,
+    pub language: Language,
+    pub config: Option<String>,
+    pub tile_size: f32,
+    pub difficulty: Difficulty,
+    pub computer: Option<Player>,
+    pub order_engine: Option<String>,
+    pub chaos_engine: Option<String>,
+    pub move_time: u64,
+    pub seed: Option<u64>,
+    pub seed_from: Option<String>,
+    pub book: Option<String>,
+    pub book_plies: usize,
+    pub save: Option<String>,
+    pub export_json: Option<String>,
+    pub diagram: Option<String>,
+    pub puzzles: Option<String>,
+    pub analyse: bool,
+    pub analysis_file: Option<String>,
+    pub analysis_time: u64,
+    pub replay: Option<String>,
+    pub replay_speed: u64,
+    pub time_control: Option<TimeControl>,
+    pub match_pairs: Option<usize>,
+    pub match_file: Option<String>,
+    pub connect: Option<String>,
+}
+
+impl Options {
+    pub fn default_options() -> Self {
+        Self {
+            ai_vs_ai_demo: false,
+            disable_gui: false,
+            disable_emoji: false,
+            disable_color_and_emoji: false,
+            force_color: false,
+            theme_name: "default".to_string(),
+            theme: Theme::default(),
+            language_name: None,
+            language: Language::English,
+            config: None,
+            tile_size: DEFAULT_TILE_SIZE,
+            difficulty: Difficulty::Intermediate,
+            computer: None,
+            order_engine: None,
+            chaos_engine: None,
+            move_time: 1000,
+            seed: None,
+            seed_from: None,
+            book: None,
+            book_plies: 6,
+            save: None,
+            export_json: None,
+            diagram: None,
+            puzzles: None,
+            analyse: false,
+            analysis_file: None,
+            analysis_time: 200,
+            replay: None,
+            replay_speed: 1000,
+            time_control: None,
+            match_pairs: None,
+            match_file: None,
+            connect: None,
+        }
+    }
+}
+
+pub fn parse_player(name: &str) -> Result<Player, String> {
+    if name.eq_ignore_ascii_case("order") {
+        return Ok(Order);
+    }
+    if name.eq_ignore_ascii_case("chaos") {
+        return Ok(Chaos);
+    }
+    return Err(format!("Unknown player \"{}\". Expected order or chaos", name));
+}
+
+impl Options {
+    pub fn seed(&self) -> u64 {
+        return self.seed.unwrap_or_default();
+    }
+
+    /// A separate seed for each side, so that both agents draw from their own stream of random numbers.
+    pub fn agent_seed(&self, player: Player) -> u64 {
+        match player {
+            Order => self.seed(),
+            Chaos => self.seed().wrapping_add(1)
+        }
+    }
+
+    pub fn external_engine(&self, player: Player) -> Option<&String> {
+        match player {
+            Order => self.order_engine.as_ref(),
+            Chaos => self.chaos_engine.as_ref()
+        }
+    }
+}
+
+/// The command line merged with the configuration file.
+pub fn read_options() -> (Command, Options) {
+    let matches = Cli::clap().get_matches();
+    let cli = Cli::from_clap(&matches);
+    let command = cli.command();
+    let mut options = command.options();
+    options.config = cli.config;
+    let command_matches = matches.subcommand_matches(command.name()).unwrap_or(&matches);
+    let mut preferences = Preferences::default();
+    let mut ignored = None;
+    if let Some(path) = preferences_path(&options) {
+        let mut merged = options.clone();
+        match Preferences::load(&path).map_err(str::to_string).and_then(|loaded| loaded.apply(&mut merged, command_matches).map(|_| loaded)) {
+            Ok(loaded) => {
+                options = merged;
+                preferences = loaded;
+            }
+            Err(v) => ignored = Some((path, v))
+        }
+    }
+    options.language = Language::detect();
+    if let Some(name) = &options.language_name {
+        match Language::parse(name) {
+            Ok(language) => options.language = language,
+            Err(v) => println!("{}", options.language.text(v))
+        }
+    }
+    let language = options.language;
+    if let Some((path, v)) = ignored {
+        println!("{}", language.format("Ignoring {}: {}", &[&path.display(), &language.text(&v)]));
+    }
+    match preferences.theme_named(&options.theme_name) {
+        Some(theme) => options.theme = theme,
+        None => println!("{}", language.format("Unknown theme \"{}\". Expected one of {} or a theme from the configuration file.", &[&options.theme_name, &BUILT_IN_THEMES.join(", ")]))
+    }
+    if let Some(path) = &options.seed_from {
+        match GameRecord::load(path).map(|record| record.seed()) {
+            Ok(Some(seed)) => options.seed = Some(seed),
+            Ok(None) => println!("{}", language.format("The game record {} does not contain a seed.", &[path])),
+            Err(v) => println!("{}", language.text(v))
+        }
+    }
+    if options.seed.is_none() {
+        options.seed = Some(rand::random());
+    }
+    return (command, options);
+}
@@ -0,0 +1,246 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sha2::{Digest, Sha256};
+use crate::notation::{move_fmt, parse_move};
+use crate::players::Player;
+use crate::record::{player_name, GameRecord};
+use crate::state::{GameState, Move};
+
+/// When a move was sent and what its player wrote alongside it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveNote {
+    pub time: DateTime<Utc>,
+    pub comment: String,
+}
+
+/// A game played by sending a file back and forth. Each side loads the file, adds one move and passes it on.
+///
+/// The file is a game record whose moves carry the time they were played and an optional comment,
+/// with an HMAC-SHA256 of everything else keyed with a secret both players share. A file that was damaged or changed
+/// by anyone who does not know the secret is refused.
+///
+/// ```text
+/// [Order "Ada"]
+/// [Chaos "Bob"]
+/// [Seed "0"]
+/// [Result "*"]
+/// [Checksum "9f86d0..."]
+///
+/// X A1 2026-10-19T08:15:00Z
+/// O C3 2026-10-19T21:02:41Z Your move, Ada
+/// ```
+pub struct Correspondence {
+    pub record: GameRecord,
+    /// One note for every move in the record.
+    pub notes: Vec<MoveNote>,
+    secret: String,
+}
+
+impl Correspondence {
+    pub fn new(order_name: &str, chaos_name: &str, secret: &str) -> Self {
+        return Self { record: GameRecord::new(order_name, chaos_name, 0), notes: Vec::new(), secret: secret.to_string() };
+    }
+
+    pub fn game_state(&self) -> GameState {
+        return self.record.final_state().unwrap_or_else(|_| GameState::default_new());
+    }
+
+    pub fn is_over(&self) -> bool {
+        return self.record.tag("Result").is_some_and(|result| result != "*");
+    }
+
+    /// Adds a move by `player`, who must be the one to move in the current position.
+    pub fn play(&mut self, player: Player, played_move: Move, comment: &str, time: DateTime<Utc>) -> Result<(), &'static str> {
+        let mut game_state = self.game_state();
+        if self.is_over() {
+            return Err("The game is already over");
+        }
+        if player != game_state.turn_player {
+            return Err("It is not your turn");
+        }
+        if self.notes.last().is_some_and(|note| note.time > time) {
+            return Err("The move is older than the last one");
+        }
+        game_state.play(played_move.0, played_move.1)?;
+        self.record.moves.push(played_move);
+        self.notes.push(MoveNote { time, comment: comment.lines().next().unwrap_or_default().trim().to_string() });
+        if game_state.is_in_order() {
+            self.record.set_winner(Player::Order);
+        } else if !game_state.can_order_win() {
+            self.record.set_winner(Player::Chaos);
+        }
+        return Ok(());
+    }
+
+    /// Gives up the game for `player`, who must be the one to move.
+    pub fn resign(&mut self, player: Player) -> Result<(), &'static str> {
+        if self.is_over() {
+            return Err("The game is already over");
+        }
+        if player != self.game_state().turn_player {
+            return Err("It is not your turn");
+        }
+        self.record.set_resignation(player);
+        return Ok(());
+    }
+
+    /// The file contents without the checksum, which is what the checksum covers.
+    fn checked_text(&self) -> String {
+        let mut text = String::new();
+        for (name, value) in self.record.tags.iter().filter(|(name, _)| name != "Checksum") {
+            text.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        text.push('\n');
+        for (played_move, note) in self.record.moves.iter().zip(&self.notes) {
+            text.push_str(&format!("{} {}", move_fmt(played_move), note.time.to_rfc3339_opts(SecondsFormat::Secs, true)));
+            if !note.comment.is_empty() {
+                text.push_str(&format!(" {}", note.comment));
+            }
+            text.push('\n');
+        }
+        return text;
+    }
+
+    fn checksum(&self) -> String {
+        return hmac_sha256(self.secret.as_bytes(), self.checked_text().as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+    }
+
+    pub fn to_text(&self) -> String {
+        let checked = self.checked_text();
+        let (tags, moves) = checked.split_once("\n\n").unwrap_or((&checked, ""));
+        return format!("{}\n[Checksum \"{}\"]\n\n{}", tags, self.checksum(), moves);
+    }
+
+    pub fn parse(text: &str, secret: &str) -> Result<Self, &'static str> {
+        let mut game = Self { record: GameRecord::default(), notes: Vec::new(), secret: secret.to_string() };
+        let mut move_lines = Vec::new();
+        let mut tag_lines = String::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if line.starts_with('[') {
+                tag_lines.push_str(line);
+                tag_lines.push('\n');
+            } else {
+                move_lines.push(line);
+            }
+        }
+        game.record = GameRecord::parse(&tag_lines)?;
+        for line in move_lines {
+            let mut parts = line.splitn(4, ' ');
+            let played_move = parse_move(&format!("{} {}", parts.next().unwrap_or_default(), parts.next().unwrap_or_default()))?;
+            let time = parts.next().and_then(|time| DateTime::parse_from_rfc3339(time).ok()).ok_or("Every move needs the time it was played")?;
+            game.record.moves.push(played_move);
+            game.notes.push(MoveNote { time: time.with_timezone(&Utc), comment: parts.next().unwrap_or_default().to_string() });
+        }
+        if !game.record.tag("Checksum").is_some_and(|checksum| equal_in_constant_time(checksum.as_bytes(), game.checksum().as_bytes())) {
+            return Err("The game file was changed or the secret is wrong: its checksum does not match");
+        }
+        game.record.final_state()?;
+        if game.notes.windows(2).any(|pair| pair[0].time > pair[1].time) {
+            return Err("The moves in the game file are out of order");
+        }
+        return Ok(game);
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), &'static str> {
+        return fs::write(path, self.to_text()).map_err(|_| "Could not write the game file");
+    }
+
+    pub fn load(path: &str, secret: &str) -> Result<Self, &'static str> {
+        let text = fs::read_to_string(path).map_err(|_| "Could not read the game file")?;
+        return Self::parse(&text, secret);
+    }
+
+    /// Loads the game file, or starts a new game if there is none yet.
+    pub fn load_or_new(path: &str, secret: &str) -> Result<Self, &'static str> {
+        if !Path::new(path).exists() {
+            return Ok(Self::new(player_name(&Player::Order), player_name(&Player::Chaos), secret));
+        }
+        return Self::load(path, secret);
+    }
+}
+
+/// HMAC-SHA256 as described in RFC 2104: SHA-256 over the key padded to one block, applied twice with different padding.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let inner = Sha256::new().chain_update(block.map(|byte| byte ^ 0x36)).chain_update(message).finalize();
+    let outer = Sha256::new().chain_update(block.map(|byte| byte ^ 0x5c)).chain_update(inner).finalize();
+    return outer.into();
+}
+
+/// Compares without stopping at the first difference, so that the time taken does not give away how much of a checksum was right.
+fn equal_in_constant_time(a: &[u8], b: &[u8]) -> bool {
+    return a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0;
+}
+
+#[cfg(test)]
+mod correspondence_test {
+    use chrono::{TimeZone, Utc};
+    use sha2::{Digest, Sha256};
+    use crate::correspondence::{hmac_sha256, Correspondence};
+    use crate::players::Player::{Chaos, Order};
+    use crate::tile::Tile::{Blue, Red};
+
+    fn two_moves() -> Correspondence {
+        let mut game = Correspondence::new("Ada", "Bob", "open sesame");
+        game.play(Order, ((0, 0), Red), "", Utc.with_ymd_and_hms(2026, 10, 19, 8, 15, 0).unwrap()).expect("");
+        game.play(Chaos, ((2, 2), Blue), "Your move, Ada", Utc.with_ymd_and_hms(2026, 10, 19, 21, 2, 41).unwrap()).expect("");
+        return game;
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game = two_moves();
+        let text = game.to_text();
+        assert!(text.contains("O C3 2026-10-19T21:02:41Z Your move, Ada\n"));
+        let loaded = Correspondence::parse(&text, "open sesame").expect("");
+        assert_eq!(loaded.record.moves, game.record.moves);
+        assert_eq!(loaded.notes, game.notes);
+        assert_eq!(loaded.game_state().turn_player, Order);
+    }
+
+    #[test]
+    fn test_refuses_damaged_files_and_moves_out_of_turn() {
+        let mut game = two_moves();
+        let text = game.to_text();
+        assert!(Correspondence::parse(&text.replace("O C3", "O C4"), "open sesame").is_err());
+        assert!(Correspondence::parse(&text.replace("[Chaos \"Bob\"]", "[Chaos \"Eve\"]"), "open sesame").is_err());
+        assert!(Correspondence::parse(&text.replace("Your move, Ada", "Resign, Ada"), "open sesame").is_err());
+        assert!(Correspondence::parse(&text, "sesame").is_err());
+
+        let later = Utc.with_ymd_and_hms(2026, 10, 20, 7, 0, 0).unwrap();
+        assert_eq!(game.play(Chaos, ((3, 3), Red), "", later), Err("It is not your turn"));
+        assert_eq!(game.play(Order, ((2, 2), Red), "", later), Err("Space Already Occupied"));
+        assert_eq!(game.resign(Chaos), Err("It is not your turn"));
+        game.resign(Order).expect("");
+        assert!(game.is_over());
+        assert_eq!(game.play(Order, ((3, 3), Red), "", later), Err("The game is already over"));
+    }
+
+    #[test]
+    fn test_refuses_edits_with_a_plain_checksum() {
+        let game = two_moves();
+        let mut forged = two_moves();
+        forged.notes[1].comment = "I resign".to_string();
+        let plain_checksum: String = Sha256::digest(forged.checked_text().as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let text = forged.to_text().replace(&forged.checksum(), &plain_checksum);
+        assert!(text.contains(&plain_checksum));
+        assert_eq!(Correspondence::parse(&text, "open sesame").err(), Some("The game file was changed or the secret is wrong: its checksum does not match"));
+        assert!(Correspondence::parse(&game.to_text(), "open sesame").is_ok());
+    }
+
+    #[test]
+    fn test_hmac_matches_rfc_4231() {
+        let hex = |bytes: [u8; 32]| bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        assert_eq!(hex(hmac_sha256(b"Jefe", b"what do ya want for nothing?")), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+        assert_eq!(hex(hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")), "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+    }
+}
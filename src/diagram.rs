@@ -0,0 +1,387 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use crate::notation::{parse_board, parse_coordinates};
+use crate::record::GameRecord;
+use crate::state::{GameState, BOARD_SIZE};
+use crate::tile::Tile::{Blue, Empty, Red};
+
+/// An arrow from one cell to another, e.g. to show a plan or a threat.
+pub type Arrow = ((usize, usize), (usize, usize));
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Color(u8, u8, u8, u8);
+
+const BACKGROUND: Color = Color(240, 217, 181, 255);
+const GRID: Color = Color(90, 70, 50, 255);
+const LABEL: Color = Color(90, 70, 50, 255);
+const RED: Color = Color(200, 30, 30, 255);
+const BLUE: Color = Color(30, 70, 200, 255);
+const LAST_MOVE: Color = Color(250, 235, 120, 255);
+const WINNING_LINE: Color = Color(40, 170, 40, 140);
+const ARROW: Color = Color(230, 60, 20, 200);
+
+/// Everything a diagram is drawn from. Positions are in pixels.
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+    Rect { x: f32, y: f32, width: f32, height: f32, fill: Color },
+    Line { from: (f32, f32), to: (f32, f32), width: f32, color: Color },
+    Ring { center: (f32, f32), radius: f32, width: f32, color: Color },
+    Polygon { points: Vec<(f32, f32)>, fill: Color },
+    /// Centred on `center`. Only board coordinates are ever written, which the bitmap font covers.
+    Text { center: (f32, f32), text: String, size: f32, color: Color },
+}
+
+/// A picture of a position that can be written as SVG or PNG without a window or display server.
+pub struct Diagram {
+    pub game_state: GameState,
+    pub coordinates: bool,
+    pub last_move: Option<(usize, usize)>,
+    pub winning_line: Option<Vec<(usize, usize)>>,
+    pub arrows: Vec<Arrow>,
+    /// Width of one cell in pixels.
+    pub cell_size: u32,
+}
+
+impl Diagram {
+    pub fn new(game_state: GameState) -> Self {
+        return Self {
+            game_state,
+            coordinates: true,
+            last_move: None,
+            winning_line: game_state.winning_line(),
+            arrows: Vec::new(),
+            cell_size: 64,
+        };
+    }
+
+    /// The final position of a game, with its last move marked.
+    pub fn from_record(record: &GameRecord) -> Result<Self, &'static str> {
+        let mut diagram = Self::new(record.final_state()?);
+        diagram.last_move = record.moves.last().map(|(coordinates, _)| *coordinates);
+        return Ok(diagram);
+    }
+
+    /// Takes a board written like `X...../....../....../....../....../......` or the path of a saved game.
+    pub fn from_position_or_file(input: &str) -> Result<Self, &'static str> {
+        if let Ok(game_state) = parse_board(input) {
+            return Ok(Self::new(game_state));
+        }
+        return Self::from_record(&GameRecord::load(input)?);
+    }
+
+    fn margin(&self) -> f32 {
+        let cell = self.cell_size as f32;
+        return if self.coordinates { cell * 0.5 } else { cell * 0.1 };
+    }
+
+    /// Width and height of the picture, which is square.
+    pub fn size(&self) -> u32 {
+        return (self.cell_size as f32 * BOARD_SIZE as f32 + 2.0 * self.margin()).ceil() as u32;
+    }
+
+    fn cell_center(&self, (row, column): (usize, usize)) -> (f32, f32) {
+        let cell = self.cell_size as f32;
+        return (self.margin() + (column as f32 + 0.5) * cell, self.margin() + (row as f32 + 0.5) * cell);
+    }
+
+    fn shapes(&self) -> Vec<Shape> {
+        let cell = self.cell_size as f32;
+        let margin = self.margin();
+        let board = cell * BOARD_SIZE as f32;
+        let size = self.size() as f32;
+        let mut shapes = vec![Shape::Rect { x: 0.0, y: 0.0, width: size, height: size, fill: BACKGROUND }];
+        if let Some((row, column)) = self.last_move {
+            shapes.push(Shape::Rect { x: margin + column as f32 * cell, y: margin + row as f32 * cell, width: cell, height: cell, fill: LAST_MOVE });
+        }
+        for index in 0..=BOARD_SIZE {
+            let offset = margin + index as f32 * cell;
+            shapes.push(Shape::Line { from: (offset, margin), to: (offset, margin + board), width: 2.0, color: GRID });
+            shapes.push(Shape::Line { from: (margin, offset), to: (margin + board, offset), width: 2.0, color: GRID });
+        }
+        if let Some(cells) = &self.winning_line {
+            if let (Some(first), Some(last)) = (cells.first(), cells.last()) {
+                shapes.push(Shape::Line { from: self.cell_center(*first), to: self.cell_center(*last), width: cell * 0.4, color: WINNING_LINE });
+            }
+        }
+        for (row, tiles) in self.game_state.board.iter().enumerate() {
+            for (column, tile) in tiles.iter().enumerate() {
+                let (x, y) = self.cell_center((row, column));
+                let reach = cell * 0.28;
+                match tile {
+                    Red => {
+                        shapes.push(Shape::Line { from: (x - reach, y - reach), to: (x + reach, y + reach), width: cell * 0.12, color: RED });
+                        shapes.push(Shape::Line { from: (x - reach, y + reach), to: (x + reach, y - reach), width: cell * 0.12, color: RED });
+                    }
+                    Blue => shapes.push(Shape::Ring { center: (x, y), radius: reach * 1.1, width: cell * 0.12, color: BLUE }),
+                    Empty => {}
+                }
+            }
+        }
+        for (from, to) in &self.arrows {
+            shapes.extend(arrow_shapes(self.cell_center(*from), self.cell_center(*to), cell));
+        }
+        if self.coordinates {
+            for index in 0..BOARD_SIZE {
+                let offset = margin + (index as f32 + 0.5) * cell;
+                let letter = ((b'A' + index as u8) as char).to_string();
+                shapes.push(Shape::Text { center: (offset, margin / 2.0), text: letter.clone(), size: margin * 0.5, color: LABEL });
+                shapes.push(Shape::Text { center: (offset, size - margin / 2.0), text: letter, size: margin * 0.5, color: LABEL });
+                shapes.push(Shape::Text { center: (margin / 2.0, offset), text: (index + 1).to_string(), size: margin * 0.5, color: LABEL });
+                shapes.push(Shape::Text { center: (size - margin / 2.0, offset), text: (index + 1).to_string(), size: margin * 0.5, color: LABEL });
+            }
+        }
+        return shapes;
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.size();
+        let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n", size);
+        for shape in self.shapes() {
+            let element = match shape {
+                Shape::Rect { x, y, width, height, fill } => {
+                    format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>", x, y, width, height, svg_paint("fill", fill))
+                }
+                Shape::Line { from, to, width, color } => {
+                    format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" {}/>", from.0, from.1, to.0, to.1, width, svg_paint("stroke", color))
+                }
+                Shape::Ring { center, radius, width, color } => {
+                    format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke-width=\"{}\" {}/>", center.0, center.1, radius, width, svg_paint("stroke", color))
+                }
+                Shape::Polygon { points, fill } => {
+                    let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+                    format!("<polygon points=\"{}\" {}/>", points.join(" "), svg_paint("fill", fill))
+                }
+                Shape::Text { center, text, size, color } => {
+                    format!("<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" {}>{}</text>", center.0, center.1, size, svg_paint("fill", color), text)
+                }
+            };
+            svg.push_str("  ");
+            svg.push_str(&element);
+            svg.push('\n');
+        }
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+
+    /// Draws the picture into RGBA pixels, four by four samples per pixel.
+    fn rasterise(&self) -> Vec<u8> {
+        let size = self.size() as usize;
+        let mut pixels = vec![255; size * size * 4];
+        for shape in self.shapes().into_iter().flat_map(expand_text) {
+            let (left, top, right, bottom) = bounds(&shape);
+            let color = paint(&shape);
+            for y in (top.floor().max(0.0) as usize)..(bottom.ceil() as usize).min(size) {
+                for x in (left.floor().max(0.0) as usize)..(right.ceil() as usize).min(size) {
+                    let mut covered = 0;
+                    for sample in 0..16 {
+                        let point = (x as f32 + (sample % 4) as f32 * 0.25 + 0.125, y as f32 + (sample / 4) as f32 * 0.25 + 0.125);
+                        if contains(&shape, point) {
+                            covered += 1;
+                        }
+                    }
+                    if covered > 0 {
+                        let alpha = color.3 as f32 / 255.0 * covered as f32 / 16.0;
+                        let pixel = &mut pixels[(y * size + x) * 4..(y * size + x) * 4 + 3];
+                        for (channel, value) in pixel.iter_mut().zip([color.0, color.1, color.2]) {
+                            *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
+                        }
+                    }
+                }
+            }
+        }
+        return pixels;
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, &'static str> {
+        let size = self.size();
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, size, size);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|_| "Could not encode the diagram")?;
+        writer.write_image_data(&self.rasterise()).map_err(|_| "Could not encode the diagram")?;
+        writer.finish().map_err(|_| "Could not encode the diagram")?;
+        return Ok(bytes);
+    }
+
+    /// Writes a PNG if the path ends in `.png` and SVG otherwise.
+    pub fn save(&self, path: &str) -> Result<(), &'static str> {
+        let bytes = if path.to_lowercase().ends_with(".png") { self.to_png()? } else { self.to_svg().into_bytes() };
+        return fs::write(path, bytes).map_err(|_| "Could not write the diagram");
+    }
+}
+
+/// Parses an arrow such as `A1-C3`.
+pub fn parse_arrow(input: &str) -> Result<Arrow, String> {
+    let (from, to) = input.split_once('-').ok_or_else(|| format!("Expected an arrow like A1-C3 but got \"{}\"", input))?;
+    return Ok((parse_coordinates(from)?, parse_coordinates(to)?));
+}
+
+fn arrow_shapes(from: (f32, f32), to: (f32, f32), cell: f32) -> Vec<Shape> {
+    let length = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt().max(1.0);
+    let direction = ((to.0 - from.0) / length, (to.1 - from.1) / length);
+    let normal = (-direction.1, direction.0);
+    let head = cell * 0.3;
+    let base = (to.0 - direction.0 * head, to.1 - direction.1 * head);
+    return vec![
+        Shape::Line { from, to: base, width: cell * 0.08, color: ARROW },
+        Shape::Polygon {
+            points: vec![
+                to,
+                (base.0 + normal.0 * head * 0.5, base.1 + normal.1 * head * 0.5),
+                (base.0 - normal.0 * head * 0.5, base.1 - normal.1 * head * 0.5),
+            ],
+            fill: ARROW,
+        },
+    ];
+}
+
+fn svg_paint(attribute: &str, color: Color) -> String {
+    let mut paint = format!("{}=\"#{:02x}{:02x}{:02x}\"", attribute, color.0, color.1, color.2);
+    if color.3 < 255 {
+        paint.push_str(&format!(" {}-opacity=\"{:.2}\"", attribute, color.3 as f32 / 255.0));
+    }
+    return paint;
+}
+
+fn paint(shape: &Shape) -> Color {
+    match shape {
+        Shape::Rect { fill, .. } | Shape::Polygon { fill, .. } => return *fill,
+        Shape::Line { color, .. } | Shape::Ring { color, .. } | Shape::Text { color, .. } => return *color
+    }
+}
+
+/// Left, top, right and bottom edges of the area a shape can touch.
+fn bounds(shape: &Shape) -> (f32, f32, f32, f32) {
+    match shape {
+        Shape::Rect { x, y, width, height, .. } => return (*x, *y, x + width, y + height),
+        Shape::Line { from, to, width, .. } => {
+            return (from.0.min(to.0) - width, from.1.min(to.1) - width, from.0.max(to.0) + width, from.1.max(to.1) + width);
+        }
+        Shape::Ring { center, radius, width, .. } => {
+            let reach = radius + width;
+            return (center.0 - reach, center.1 - reach, center.0 + reach, center.1 + reach);
+        }
+        Shape::Polygon { points, .. } => {
+            let xs = points.iter().map(|point| point.0);
+            let ys = points.iter().map(|point| point.1);
+            return (xs.clone().fold(f32::MAX, f32::min), ys.clone().fold(f32::MAX, f32::min), xs.fold(f32::MIN, f32::max), ys.fold(f32::MIN, f32::max));
+        }
+        Shape::Text { .. } => return (0.0, 0.0, 0.0, 0.0)
+    }
+}
+
+fn contains(shape: &Shape, (x, y): (f32, f32)) -> bool {
+    match shape {
+        Shape::Rect { x: left, y: top, width, height, .. } => return x >= *left && x < left + width && y >= *top && y < top + height,
+        Shape::Line { from, to, width, .. } => return distance_to_segment((x, y), *from, *to) <= width / 2.0,
+        Shape::Ring { center, radius, width, .. } => {
+            let distance = ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt();
+            return (distance - radius).abs() <= width / 2.0;
+        }
+        Shape::Polygon { points, .. } => {
+            let sides: Vec<f32> = points.iter().zip(points.iter().cycle().skip(1))
+                .map(|(a, b)| (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0))
+                .collect();
+            return sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0);
+        }
+        Shape::Text { .. } => return false
+    }
+}
+
+fn distance_to_segment(point: (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32 {
+    let segment = (to.0 - from.0, to.1 - from.1);
+    let length_squared = segment.0 * segment.0 + segment.1 * segment.1;
+    let t = if length_squared == 0.0 { 0.0 } else { (((point.0 - from.0) * segment.0 + (point.1 - from.1) * segment.1) / length_squared).clamp(0.0, 1.0) };
+    let closest = (from.0 + t * segment.0, from.1 + t * segment.1);
+    return ((point.0 - closest.0).powi(2) + (point.1 - closest.1).powi(2)).sqrt();
+}
+
+/// Turns text into one rectangle per lit pixel of the bitmap font, so that it can be rasterised like any other shape.
+fn expand_text(shape: Shape) -> Vec<Shape> {
+    let (center, text, size, color) = match shape {
+        Shape::Text { center, text, size, color } => (center, text, size, color),
+        shape => return vec![shape]
+    };
+    let pixel = size / GLYPH_HEIGHT as f32;
+    let width = text.len() as f32 * (GLYPH_WIDTH + 1) as f32 * pixel - pixel;
+    let mut rects = Vec::new();
+    for (index, character) in text.chars().enumerate() {
+        let rows = match glyph(character) {
+            Some(v) => v,
+            None => continue
+        };
+        let left = center.0 - width / 2.0 + index as f32 * (GLYPH_WIDTH + 1) as f32 * pixel;
+        let top = center.1 - size / 2.0;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    rects.push(Shape::Rect { x: left + column as f32 * pixel, y: top + row as f32 * pixel, width: pixel, height: pixel, fill: color });
+                }
+            }
+        }
+    }
+    return rects;
+}
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+
+/// A 5x7 font holding just the characters of board coordinates. Each row is five bits, most significant on the left.
+fn glyph(character: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    let rows = match character {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11110, 0b00001, 0b00001, 0b01110, 0b00001, 0b00001, 0b11110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        _ => return None
+    };
+    return Some(rows);
+}
+
+#[cfg(test)]
+mod diagram_test {
+    use crate::diagram::{parse_arrow, Diagram};
+    use crate::notation::parse_board;
+
+    #[test]
+    fn test_svg_shows_pieces_and_markings() {
+        let mut diagram = Diagram::new(parse_board("XXXXX./O...../....../....../....../......").expect(""));
+        diagram.last_move = Some((0, 4));
+        diagram.arrows = vec![parse_arrow("B2-D4").expect("")];
+        assert_eq!(diagram.winning_line, Some(vec![(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)]));
+        let svg = diagram.to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"448\""));
+        assert_eq!(svg.matches("stroke=\"#c81e1e\"").count(), 10);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert!(svg.contains(">F</text>"));
+
+        diagram.coordinates = false;
+        assert!(!diagram.to_svg().contains("<text"));
+        assert!(parse_arrow("B2").is_err());
+    }
+
+    #[test]
+    fn test_png_is_drawn_without_a_display() {
+        let mut diagram = Diagram::new(parse_board("X...../....../....../....../....../.....O").expect(""));
+        diagram.cell_size = 20;
+        let pixels = diagram.rasterise();
+        let size = diagram.size() as usize;
+        let at = |x: usize, y: usize| &pixels[(y * size + x) * 4..(y * size + x) * 4 + 3];
+        assert_eq!(at(1, 1), &[240, 217, 181]);
+        assert_eq!(at(20, 20), &[200, 30, 30]);
+        assert_eq!(at(size - 20, size - 20), &[240, 217, 181]);
+        let png = diagram.to_png().expect("");
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -1,158 +1,491 @@
-use std::fmt;
-use std::fmt::Formatter;
+#![allow(clippy::needless_return)]
+
+use crate::analysis::Analysis;
+use crate::clock::Clocks;
 use crate::config;
+use crate::config::{Options, DEFAULT_TILE_SIZE};
+use crate::correspondence::Correspondence;
+use crate::matches::Match;
+use crate::network::NetworkSession;
+use crate::evaluation::{evaluate, Evaluation};
 use crate::players::Player;
 use crate::players::Player::{Chaos, Order};
-use crate::state::GameState;
+use crate::state::{GameState, Move};
+use crate::puzzle::{success_rate, Puzzle, PuzzleKind, Solution};
+use crate::record::GameRecord;
+use crate::replay::Replay;
 use crate::tile::Tile::{Blue, Red, Empty};
+use crate::locale::{Language, Segment, RULES, WELCOME};
+use crate::terminal::Capabilities;
+use crate::theme::{Theme, ThemeColor};
 use crate::tile::Tile;
 
 
 
 
-pub fn show_welcome_text() {
-    let line_1;
-    let line_2: String;
-    let line_3 = "Order plays first, then turns alternate.";
-    let line_4: String;
-    let line_5: String;
-    let line_6: String;
-    let line_7 = "Six-in-a-row does not qualify as a win\n\n";
-    let line_8 = "Press any key to continue.";
-
-    if config::get().disable_color_and_emoji {
-        line_1 = "\n\n\nWelcome to the ORDER & CHAOS electronic simulator.\n\n\n";
-        line_2 = "Both players control both sets of pieces (X and O). The game starts with the board empty.".to_string();
-        line_4 = "On each turn, a player places either an X or an O on any open square. Once played, pieces cannot be moved".to_string();
-        line_5 = "Order aims to get exactly five like pieces in a row either vertically, horizontally, or diagonally.".to_string();
-        line_6 = "Chaos aims to fill the board without completion of a line of five like pieces.".to_string();
-    } else if config::get().disable_emoji {
-        line_1 = "\n\n\nWelcome to the \x1b[33mORDER\x1b[0m & \x1b[32mCHAOS\x1b[0m electronic simulator.\n\n\n";
-        line_2 = "Both players control both sets of pieces (\x1b[31mX\x1b[0m and \x1b[34mO\x1b[0m). The game starts with the board empty.".to_string();
-        line_4 = "On each turn, a player places either an \x1b[31mX\x1b[0m or an \x1b[34mO\x1b[0m on any open square. Once played, pieces cannot be moved".to_string();
-        line_5 = format!("{} aims to get exactly five like pieces in a row either vertically, horizontally, or diagonally.", player_fmt(&Order));
-        line_6 = format!("{} aims to fill the board without completion of a line of five like pieces.", player_fmt(&Chaos));
+pub fn show_welcome_text(renderer: &Renderer) {
+    let rules: Vec<String> = RULES.iter().map(|rule| renderer.message(rule)).collect();
+    println!("\n\n\n{}\n\n\n", renderer.message(WELCOME));
+    println!("{}\n\n\n{}", rules.join("\n"), renderer.language.text("Press any key to continue."));
+}
+
+pub fn show_exit_dialog(renderer: &Renderer) {
+    println!("{}", renderer.language.text("Press any key to exit the game."));
+}
+
+pub fn show_winner(renderer: &Renderer, winner: Player) {
+    println!("{}", renderer.language.format("{} Won!", &[&renderer.player(&winner)]));
+}
+
+pub fn show_clocks(renderer: &Renderer, clocks: &Clocks) {
+    println!("{}: {}   {}: {}", renderer.player(&Order), clocks.clock_fmt(Order), renderer.player(&Chaos), clocks.clock_fmt(Chaos));
+}
+
+pub fn show_time_forfeit(renderer: &Renderer, loser: Player) {
+    println!("{}", renderer.language.format("{} ran out of time.", &[&renderer.player(&loser)]));
+    show_winner(renderer, loser.opponent());
+}
+
+pub fn show_match(renderer: &Renderer, series: &Match) {
+    println!("\n{}", series.score_table(renderer.language));
+    if series.is_over() {
+        println!("{}", series.summary(renderer.language));
+    }
+}
+
+pub fn show_next_game_prompt(renderer: &Renderer) {
+    println!("{}", renderer.language.text("Press enter to start the next game, with sides swapped."));
+}
+
+pub fn show_abandonment(renderer: &Renderer, loser: Player) {
+    println!("{}", renderer.language.format("{} abandoned the game.", &[&renderer.player(&loser)]));
+    show_winner(renderer, loser.opponent());
+}
+
+pub fn show_resignation(renderer: &Renderer, loser: Player) {
+    println!("{}", renderer.language.format("{} resigned.", &[&renderer.player(&loser)]));
+    show_winner(renderer, loser.opponent());
+}
+
+pub fn show_network_game(renderer: &Renderer, session: &NetworkSession) {
+    println!("{}", renderer.language.format("Playing {} against {}", &[&renderer.player(&session.local), &session.peer]));
+}
+
+pub fn show_rematch_prompt(renderer: &Renderer) {
+    println!("{}", renderer.language.text("Play again with sides swapped? (y/n)"));
+}
+
+pub fn show_correspondence(renderer: &Renderer, game: &Correspondence) {
+    for (index, (played_move, note)) in game.record.moves.iter().zip(&game.notes).enumerate() {
+        print!("{:>3}. {} {}", index + 1, renderer.language.move_text(played_move), note.time.format("%Y-%m-%d %H:%M UTC"));
+        if !note.comment.is_empty() {
+            print!("  \"{}\"", note.comment);
+        }
+        println!();
+    }
+    let game_state = game.game_state();
+    println!("{}", renderer.board(&game_state));
+    if let Some(winner) = game.record.tag("Result").and_then(|result| config::parse_player(result).ok()) {
+        match game.record.tag("Termination") {
+            Some("Resignation") => show_resignation(renderer, winner.opponent()),
+            Some("Abandoned") => show_abandonment(renderer, winner.opponent()),
+            _ => show_winner(renderer, winner)
+        }
+        return;
+    }
+    println!("{}", renderer.language.format("{} to move.", &[&renderer.player(&game_state.turn_player)]));
+}
+
+pub fn show_comment_prompt(renderer: &Renderer) {
+    println!("{}", renderer.language.text("Add a comment for your opponent, or press enter to skip:"));
+}
+
+pub fn show_correspondence_saved(renderer: &Renderer, path: &str, opponent: Player) {
+    println!("{}", renderer.language.format("Saved {}. Send it to {} for the next move.", &[&path, &renderer.player(&opponent)]));
+}
+
+pub fn show_seed(renderer: &Renderer, seed: u64) {
+    println!("{}", renderer.language.format("Seed: {}", &[&seed]));
+}
+
+pub fn show_game_state(renderer: &Renderer, game_state: &GameState) {
+    println!("{}", renderer.board(game_state));
+    println!("{}", renderer.evaluation(&evaluate(game_state)));
+    show_threats(renderer, game_state);
+}
+
+fn show_threats(renderer: &Renderer, game_state: &GameState) {
+    if game_state.is_in_order() {
+        return;
+    }
+    let threats = game_state.threats();
+    if threats.is_empty() {
+        return;
+    }
+    let language = renderer.language;
+    let winning_moves: Vec<String> = threats.winning_moves.iter().map(|winning_move| language.move_text(winning_move)).collect();
+    if Order.eq(&game_state.turn_player) {
+        println!("\n{} {}", renderer.highlight(language.text("Hint:")), language.format("{} can win right now with {}", &[&renderer.player(&Order), &winning_moves.join(", ")]));
+        return;
+    }
+    let forced_cells: Vec<String> = threats.forced_cells.iter().map(|cell| language.coordinates(cell)).collect();
+    println!("\n{} {}", renderer.highlight(language.text("Warning:")), language.format("{} threatens to win with {}", &[&renderer.player(&Order), &winning_moves.join(", ")]));
+    println!("{}", language.format("{} is forced to neutralise {}", &[&renderer.player(&Chaos), &forced_cells.join(", ")]));
+    match threats.saving_moves.first() {
+        Some(saving_move) => println!("{}", language.format("{} can stop every threat, e.g. with {}", &[&renderer.player(&Chaos), &language.move_text(saving_move)])),
+        None => println!("{}", language.format("{} cannot stop every threat.", &[&renderer.player(&Chaos)]))
+    }
+}
+
+pub fn show_analysis(renderer: &Renderer, analysis: &Analysis, record: &GameRecord) {
+    println!("\n{}", analysis.to_text(record, renderer.language));
+}
+
+pub fn show_best_moves(renderer: &Renderer, moves: &[(Move, i32)]) {
+    let moves: Vec<String> = moves.iter().map(|(candidate, score)| format!("{} ({:+})", renderer.language.move_text(candidate), score)).collect();
+    println!("\n{}", renderer.language.format("Best moves: {}", &[&moves.join(", ")]));
+}
+
+pub fn show_solution(renderer: &Renderer, game_state: &GameState, solution: &Solution, depth: usize) {
+    println!("{}\n", renderer.board(game_state));
+    let language = renderer.language;
+    let moves: Vec<String> = solution.moves.iter().map(|solution_move| language.move_text(solution_move)).collect();
+    let text = match (game_state.turn_player, solution.order_wins_in) {
+        (Order, Some(wins_in)) => language.format("{} wins in {} with {}", &[&renderer.player(&Order), &wins_in, &moves.join(", ")]),
+        (Chaos, Some(wins_in)) => language.format("{} wins in {} whatever {} plays", &[&renderer.player(&Order), &wins_in, &renderer.player(&Chaos)]),
+        (Order, None) => language.format("{} cannot force a win within {} moves", &[&renderer.player(&Order), &depth]),
+        (Chaos, None) => language.format("{} holds out for {} moves with {}", &[&renderer.player(&Chaos), &depth, &moves.join(", ")])
+    };
+    println!("{}", text);
+}
+
+pub fn show_replay(renderer: &Renderer, replay: &Replay) {
+    let language = renderer.language;
+    println!("{}", language.format("Move {} of {}", &[&replay.position(), &replay.len()]));
+    if let Some(last_move) = replay.last_move() {
+        println!("{}", language.format("Last move: {}", &[&language.move_text(&last_move)]));
+    }
+    println!("{}", renderer.board(replay.game_state()));
+    println!("{}", language.text("n: next, b: back, s: start, e: end, p: play to the end, q: quit"));
+}
+
+pub fn show_puzzle(renderer: &Renderer, puzzle: &Puzzle, number: usize, total: usize) {
+    println!("{}", renderer.language.format("Puzzle {} of {}: {}", &[&number, &total, &renderer.puzzle_kind(&puzzle.kind)]));
+    println!("{}", renderer.board(&puzzle.position));
+    println!("{}", renderer.language.text("Enter your move, or q to stop training."));
+}
+
+pub fn show_puzzle_result(renderer: &Renderer, puzzle: &Puzzle, correct: bool) {
+    if correct {
+        println!("{}", renderer.language.text("Correct!"));
     } else {
-        line_1 = "\n\n\nWelcome to the \x1b[33mＯＲＤＥＲ\x1b[0m　＆　\x1b[32mＣＨＡＯＳ\x1b[0m electronic simulator.\n\n\n";
-        line_2 = format!("Both players control both sets of pieces ({} and {}). The game starts with the board empty.", tile_fmt(&Red), tile_fmt(&Blue));
-        line_4 = format!("On each turn, a player places either an {} or an {} on any open square. Once played, pieces cannot be moved", tile_fmt(&Red), tile_fmt(&Blue));
-        line_5 = format!("{} aims to get exactly five like pieces in a row either vertically, horizontally, or diagonally.", player_fmt(&Order));
-        line_6 = format!("{} aims to fill the board without completion of a line of five like pieces.", player_fmt(&Chaos));
+        println!("{}", renderer.language.format("Not quite. The answer was {}", &[&renderer.language.move_text(&puzzle.solution)]));
     }
-    println!("{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}", line_1, line_2, line_3, line_4, line_5, line_6, line_7, line_8);
 }
 
-pub fn show_exit_dialog() {
-    println!("Press any key to exit the game.");
+pub fn show_success_rate(renderer: &Renderer, puzzles: &[Puzzle]) {
+    let attempts: u32 = puzzles.iter().map(|puzzle| puzzle.attempts).sum();
+    println!("{}", renderer.language.format("Success rate: {}% over {} attempts", &[&success_rate(puzzles), &attempts]));
 }
 
-pub fn show_winner(winner: Player) {
-    println!("{} Won!", player_fmt(&winner));
+pub fn show_input_prompt(renderer: &Renderer) {
+    let language = renderer.language;
+    println!("{}\n", language.text("Please select which pawn should be placed in which location on the board."));
+    println!("{}\n", language.text("Examples: \nX A1\nO 5F"));
+    println!("{}\n", language.text("Type resign to give up, save <file> to write the board as a diagram or load <file> to continue from one."));
 }
 
-pub fn show_game_state(game_state: &GameState) {
-    println!("{}", game_state);
+pub fn show_diagram_saved(renderer: &Renderer, path: &str) {
+    println!("{}", renderer.language.format("Saved the board to {}", &[&path]));
 }
 
-pub fn show_input_prompt() {
-    println!("Please select which pawn should be placed in which location on the board.\n");
-    println!("Examples: \nX A1\nO 5F\n");
+/// Prints an error, translated if the catalogue knows it.
+pub fn show_error_message(renderer: &Renderer, e: &str) {
+    println!("{}", renderer.language.text(e));
 }
 
-pub fn show_error_message(e: &str) {
-    println!("{}", e);
+/// The full-width forms of letters, which line up with emoji.
+fn full_width(text: &str) -> String {
+    return text.chars().map(|character| match character {
+        '!'..='~' => char::from_u32(character as u32 + 0xFEE0).unwrap_or(character),
+        _ => character
+    }).collect();
 }
 
+/// Clears the screen, unless the output goes to a file or pipe, where the escape sequence would only be noise.
 pub fn clear_output() {
     let term = console::Term::stdout();
+    if !term.is_term() {
+        return;
+    }
     let _ = term.clear_screen();
 }
 
-fn player_fmt(player: &Player) -> &'static str {
-    if config::get().disable_color_and_emoji {
-        return match *player {
-            Order => "Order",
-            Chaos => "Chaos"
+/// How text output is styled. Each output carries its own, so one process can log plain text while drawing
+/// coloured boards in a terminal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Renderer {
+    /// ANSI colours for the players and the board.
+    pub color: bool,
+    /// Emoji pieces and full-width column labels.
+    pub emoji: bool,
+    /// Size of a board cell in the window, in points.
+    pub tile_size: f32,
+    pub theme: Theme,
+    pub language: Language,
+}
+
+impl Renderer {
+    /// The richest style the terminal can show, unless the options turn some of it off.
+    pub fn from_options(options: &Options) -> Self {
+        let capabilities = if options.force_color { Capabilities::full() } else { Capabilities::detect() };
+        return Self {
+            color: capabilities.color && !options.disable_color_and_emoji,
+            emoji: capabilities.emoji && !(options.disable_color_and_emoji || options.disable_emoji),
+            tile_size: options.tile_size,
+            theme: options.theme,
+            language: options.language,
         };
     }
-    match *player {
-        Order => "\x1b[33mOrder\x1b[0m",
-        Chaos => "\x1b[32mChaos\x1b[0m"
+
+    /// Letters only in the default theme and English, for logs and files. The terminal is not consulted.
+    pub fn plain() -> Self {
+        return Self { color: false, emoji: false, tile_size: DEFAULT_TILE_SIZE, theme: Theme::default(), language: Language::English };
+    }
+
+    fn paint(&self, color: ThemeColor, text: &str) -> String {
+        if !self.color {
+            return text.to_string();
+        }
+        return format!("\x1b[{}m{}\x1b[0m", color.foreground(), text);
+    }
+
+    fn title(&self) -> String {
+        let order = self.language.player(&Order).to_uppercase();
+        let chaos = self.language.player(&Chaos).to_uppercase();
+        if self.emoji {
+            return format!("{}　＆　{}", self.paint(self.theme.order, &full_width(&order)), self.paint(self.theme.chaos, &full_width(&chaos)));
+        }
+        return format!("{} & {}", self.paint(self.theme.order, &order), self.paint(self.theme.chaos, &chaos));
+    }
+
+    /// A message of the catalogue with its pieces, players and title drawn in this style.
+    fn message(&self, message: &str) -> String {
+        let mut text = String::new();
+        for segment in self.language.segments(message) {
+            match segment {
+                Segment::Text(part) => text.push_str(part),
+                Segment::Piece(tile) => text.push_str(&self.piece(&tile)),
+                Segment::Player(player) => text.push_str(&self.player(&player)),
+                Segment::Title => text.push_str(&self.title())
+            }
+        }
+        return text;
     }
-}
 
-fn tile_fmt(tile: &Tile) -> &str {
-    if config::get().disable_color_and_emoji || config::get().disable_emoji {
+    pub fn puzzle_kind(&self, kind: &PuzzleKind) -> String {
+        match kind {
+            PuzzleKind::OrderWinsIn(moves) => self.language.format("{} to win in {}", &[&self.language.player(&Order), moves]),
+            PuzzleKind::ChaosSaves => self.language.format("{} to stop all threats", &[&self.language.player(&Chaos)])
+        }
+    }
+
+    /// A piece as it is named in running text.
+    fn piece(&self, tile: &Tile) -> String {
+        if self.emoji && !self.themed_emoji() {
+            return self.tile(tile).to_string();
+        }
+        if self.emoji {
+            return self.paint(self.theme.tile(tile), self.tile(tile));
+        }
         return match tile {
-            Empty => "[ ]",
-            Blue => " O ",
-            Red => " X "
+            Empty => String::new(),
+            Blue => self.paint(self.theme.o, "O"),
+            Red => self.paint(self.theme.x, "X")
         };
     }
 
-    match tile {
-        Empty => "🟪",
-        Blue => "🔵",
-        Red => "❌"
+    pub fn player(&self, player: &Player) -> String {
+        return self.paint(self.theme.player(player), self.language.player(player));
+    }
+
+    /// Text marked as a threat, a hint or a mistake.
+    pub fn highlight(&self, text: &str) -> String {
+        return self.paint(self.theme.highlight, text);
+    }
+
+    /// Whether the emoji style draws full-width letters instead of emoji. The emoji have colours of their own,
+    /// which only match the default theme, so any other theme paints the letters in its colours.
+    fn themed_emoji(&self) -> bool {
+        return self.emoji && self.color && self.theme != Theme::default();
     }
-}
 
-impl fmt::Display for GameState {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    pub fn tile(&self, tile: &Tile) -> &'static str {
+        if !self.emoji {
+            return match tile {
+                Empty => "[ ]",
+                Blue => " O ",
+                Red => " X "
+            };
+        }
+        if self.themed_emoji() {
+            return match tile {
+                Empty => "・",
+                Blue => "Ｏ",
+                Red => "Ｘ"
+            };
+        }
+
+        match tile {
+            Empty => "🟪",
+            Blue => "🔵",
+            Red => "❌"
+        }
+    }
+
+    /// A cell of the board. Only the text colour is reset afterwards, so that the row keeps its background.
+    fn cell(&self, tile: &Tile) -> String {
+        if !self.color || (self.emoji && !self.themed_emoji()) {
+            return self.tile(tile).to_string();
+        }
+        return format!("\x1b[{}m{}\x1b[39m", self.theme.tile(tile).foreground(), self.tile(tile));
+    }
+
+    /// The board with column and row labels, followed by the player to move.
+    pub fn board(&self, game_state: &GameState) -> String {
         let mut game_board = String::from("");
-        self.show_game_board(&mut game_board);
-        self.show_turn_player(&mut game_board);
-        write!(f, "{}", game_board)
+        self.show_game_board(game_state, &mut game_board);
+        game_board.push('\n');
+        game_board.push_str(self.language.text("Turn Player: "));
+        game_board.push_str(&self.player(&game_state.turn_player));
+        return game_board;
     }
-}
 
-impl GameState {
-    fn show_column_labels(game_board: &mut String) {
-        if !(config::get().disable_color_and_emoji || config::get().disable_emoji) {
-            game_board.push_str(" ＡＢＣＤＥＦ\n");
+    pub fn evaluation(&self, evaluation: &Evaluation) -> String {
+        let favoured = if evaluation.score > 0 {
+            self.player(&Order)
+        } else if evaluation.score < 0 {
+            self.player(&Chaos)
         } else {
-            game_board.push_str("  A  B  C  D  E  F\n");
-        }
+            self.language.text("Nobody").to_string()
+        };
+        let language = self.language;
+        let mut text = language.format("Evaluation: {} ({} favoured)", &[&format!("{:+}", evaluation.score), &favoured]);
+        text.push('\n');
+        text.push_str(&language.format("Live windows: {} {} / {} {}", &[&evaluation.live_red_windows, &self.tile(&Red), &evaluation.live_blue_windows, &self.tile(&Blue)]));
+        text.push('\n');
+        text.push_str(&language.format("Open fours: {}, open threes: {}, double threats: {}", &[&evaluation.open_fours, &evaluation.open_threes, &evaluation.double_threats.len()]));
+        text.push('\n');
+        text.push_str(&language.format("Overline cells: {}, {} blocking potential: {}", &[&evaluation.overline_cells.len(), &language.player(&Chaos), &evaluation.chaos_blocking_potential]));
+        return text;
     }
 
-    fn get_turn_color(&self) -> &str {
-        if Order.eq(&self.turn_player) && !config::get().disable_color_and_emoji {
-            "\x1b[43m"
+    fn show_column_labels(&self, game_board: &mut String) {
+        let columns: String = self.language.columns().iter().collect();
+        if self.emoji {
+            game_board.push_str(&format!(" {}\n", full_width(&columns)));
         } else {
-            "\x1b[42m"
+            let columns: Vec<String> = columns.chars().map(String::from).collect();
+            game_board.push_str(&format!("  {}\n", columns.join("  ")));
         }
     }
 
-    fn show_board_with_turn_colors(game_board: &mut String, turn_color: &str, row: &[Tile; 6]) {
+    fn get_turn_color(&self, game_state: &GameState) -> String {
+        return format!("\x1b[{}m", self.theme.player(&game_state.turn_player).background());
+    }
+
+    fn show_board_with_turn_colors(&self, game_board: &mut String, turn_color: &str, row: &[Tile; 6]) {
         game_board.push_str(turn_color);
         for tile in row {
-            game_board.push_str(tile_fmt(tile));
+            game_board.push_str(&self.cell(tile));
         }
         game_board.push_str("\x1b[0m");
     }
 
-    fn show_plain_board(game_board: &mut String, row: &[Tile; 6]) {
+    fn show_plain_board(&self, game_board: &mut String, row: &[Tile; 6]) {
         for tile in row {
-            game_board.push_str(tile_fmt(tile));
+            game_board.push_str(self.tile(tile));
         }
     }
 
-    fn show_turn_player(&self, game_board: &mut String) {
-        game_board.push_str("\nTurn Player: ");
-        game_board.push_str(player_fmt(&self.turn_player));
-    }
-
-    fn show_game_board(&self, game_board: &mut String) {
-        let turn_color = self.get_turn_color();
-        Self::show_column_labels(game_board);
-        for (index, row) in self.board.iter().enumerate() {
+    fn show_game_board(&self, game_state: &GameState, game_board: &mut String) {
+        let turn_color = self.get_turn_color(game_state);
+        self.show_column_labels(game_board);
+        for (index, row) in game_state.board.iter().enumerate() {
             game_board.push_str(&(index + 1).to_string());
-            if config::get().disable_color_and_emoji {
-                Self::show_plain_board(game_board, row);
+            if self.color {
+                self.show_board_with_turn_colors(game_board, &turn_color, row);
             } else {
-                Self::show_board_with_turn_colors(game_board, turn_color, row);
+                self.show_plain_board(game_board, row);
             }
             game_board.push('\n');
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod display_test {
+    use crate::display::Renderer;
+    use crate::evaluation::evaluate;
+    use crate::players::Player::Order;
+    use crate::state::GameState;
+    use crate::theme::{Theme, ThemeColor};
+    use crate::tile::Tile::{Blue, Red};
+
+    fn position() -> GameState {
+        let mut game_state = GameState::default_new();
+        game_state.play((0, 0), Red).expect("");
+        game_state.play((1, 2), Blue).expect("");
+        return game_state;
+    }
+
+    #[test]
+    fn test_every_style() {
+        let game_state = position();
+        let plain = Renderer::plain();
+        let colored = Renderer { color: true, ..plain };
+        let emoji = Renderer { color: true, emoji: true, ..plain };
+
+        let text = plain.board(&game_state);
+        assert!(text.starts_with("  A  B  C  D  E  F\n1 X [ ][ ][ ][ ][ ]\n2[ ][ ] O [ ][ ][ ]\n"));
+        assert!(text.ends_with("Turn Player: Order"));
+        assert!(!text.contains('\x1b'));
+
+        let text = colored.board(&game_state);
+        assert!(text.contains("1\x1b[43m\x1b[31m X \x1b[39m\x1b[37m[ ]\x1b[39m"));
+        assert!(text.ends_with("Turn Player: \x1b[33mOrder\x1b[0m"));
+
+        let theme = Theme { order: ThemeColor::Rgb(255, 153, 0), ..Theme::default() };
+        let text = Renderer { theme, ..colored }.board(&game_state);
+        assert!(text.contains("1\x1b[48;2;255;153;0m\x1b[31m X "));
+        assert!(text.ends_with("Turn Player: \x1b[38;2;255;153;0mOrder\x1b[0m"));
+
+        let text = emoji.board(&game_state);
+        assert!(text.starts_with(" ＡＢＣＤＥＦ\n1\x1b[43m❌🟪🟪🟪🟪🟪\x1b[0m\n2\x1b[43m🟪🟪🔵🟪🟪🟪"));
+
+        let dusk = Theme { x: ThemeColor::Rgb(255, 153, 0), o: ThemeColor::Named(6), board: ThemeColor::Named(0), ..Theme::default() };
+        let text = Renderer { theme: dusk, ..emoji }.board(&game_state);
+        assert!(text.starts_with(" ＡＢＣＤＥＦ\n1\x1b[43m\x1b[38;2;255;153;0mＸ\x1b[39m\x1b[30m・\x1b[39m"));
+        assert!(text.contains("2\x1b[43m\x1b[30m・\x1b[39m\x1b[30m・\x1b[39m\x1b[36mＯ\x1b[39m"));
+        assert!(!text.contains('❌'));
+
+        let text = Renderer { emoji: true, ..plain }.board(&game_state);
+        assert!(text.contains("1❌🟪🟪🟪🟪🟪\n"));
+        assert!(!text.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_styles_side_by_side() {
+        let evaluation = evaluate(&position());
+        let plain = Renderer::plain();
+        let colored = Renderer { color: true, emoji: true, ..plain };
+        assert_eq!(plain.player(&Order), "Order");
+        assert_eq!(colored.player(&Order), "\x1b[33mOrder\x1b[0m");
+        assert!(plain.evaluation(&evaluation).contains(" X  / "));
+        assert!(colored.evaluation(&evaluation).contains(" ❌ / "));
+        assert_eq!(plain.title(), "ORDER & CHAOS");
+        assert_eq!(Renderer { color: true, ..plain }.title(), "\x1b[33mORDER\x1b[0m & \x1b[32mCHAOS\x1b[0m");
+        assert_eq!(plain.piece(&Red), "X");
+        assert_eq!(Renderer { color: true, ..plain }.piece(&Blue), "\x1b[34mO\x1b[0m");
+    }
+}
@@ -0,0 +1,264 @@
+#![allow(clippy::needless_return)]
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::evaluation::{quick_score, WIN_SCORE};
+use crate::players::Player::{Chaos, Order};
+use crate::state::{GameState, Move};
+use crate::tile::Tile::{Blue, Red};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Difficulty {
+    Beginner,
+    Casual,
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [Difficulty::Beginner, Difficulty::Casual, Difficulty::Intermediate, Difficulty::Advanced, Difficulty::Expert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Casual => "casual",
+            Difficulty::Intermediate => "intermediate",
+            Difficulty::Advanced => "advanced",
+            Difficulty::Expert => "expert"
+        }
+    }
+
+    /// How many plies the engine looks ahead.
+    pub fn search_depth(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 1,
+            Difficulty::Casual => 1,
+            Difficulty::Intermediate => 2,
+            Difficulty::Advanced => 3,
+            Difficulty::Expert => 4
+        }
+    }
+
+    /// The largest random amount added to or taken from the score of each candidate move.
+    pub fn evaluation_noise(&self) -> i32 {
+        match self {
+            Difficulty::Beginner => 80,
+            Difficulty::Casual => 40,
+            Difficulty::Intermediate => 15,
+            Difficulty::Advanced => 5,
+            Difficulty::Expert => 0
+        }
+    }
+
+    /// The chance of ignoring the search entirely and playing a random move.
+    pub fn blunder_probability(&self) -> f64 {
+        match self {
+            Difficulty::Beginner => 0.3,
+            Difficulty::Casual => 0.15,
+            Difficulty::Intermediate => 0.05,
+            Difficulty::Advanced => 0.01,
+            Difficulty::Expert => 0.0
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        for difficulty in Difficulty::ALL {
+            if difficulty.name().eq_ignore_ascii_case(name.trim()) {
+                return Ok(difficulty);
+            }
+        }
+        return Err(format!("Unknown difficulty \"{}\". Expected one of beginner, casual, intermediate, advanced, expert", name));
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An alpha-beta search over `GameState` that plays either side at a chosen difficulty.
+pub struct Engine {
+    pub difficulty: Difficulty,
+    /// How long a search may take; without one the engine always searches to its full depth.
+    pub time_limit: Option<Duration>,
+    pub depth: usize,
+}
+
+impl Engine {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self { difficulty, time_limit: Some(Duration::from_secs(2)), depth: difficulty.search_depth() }
+    }
+
+    /// Picks a move for the turn player, or `None` if the board is full.
+    pub fn best_move<R: Rng>(&self, game_state: &GameState, rng: &mut R) -> Option<Move> {
+        let moves = legal_moves(game_state);
+        if moves.is_empty() {
+            return None;
+        }
+        if rng.gen_bool(self.difficulty.blunder_probability()) {
+            return moves.choose(rng).copied();
+        }
+
+        let scored_moves = self.scored_moves(game_state);
+        let noise = self.difficulty.evaluation_noise();
+        let mut best: Option<(Move, i32)> = None;
+        for (candidate, score) in scored_moves {
+            let mut score = score;
+            if noise > 0 {
+                score += rng.gen_range(-noise..=noise);
+            }
+            if Chaos.eq(&game_state.turn_player) {
+                score = -score;
+            }
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((candidate, score));
+            }
+        }
+        return best.map(|(best_move, _)| best_move);
+    }
+
+    /// Scores every legal move from Order's point of view, searching as deep as the depth and time limit allow.
+    pub fn scored_moves(&self, game_state: &GameState) -> Vec<(Move, i32)> {
+        let moves = legal_moves(game_state);
+        let deadline = self.time_limit.map(|time_limit| Instant::now() + time_limit);
+        let mut scored_moves = score_moves(game_state, &moves, 1, deadline).unwrap_or_default();
+        for depth in 2..=self.depth {
+            match score_moves(game_state, &moves, depth, deadline) {
+                Some(deeper) => scored_moves = deeper,
+                None => break
+            }
+        }
+        return scored_moves;
+    }
+}
+
+/// Every empty cell, once with each tile.
+pub fn legal_moves(game_state: &GameState) -> Vec<Move> {
+    let mut moves = Vec::new();
+    for cell in game_state.empty_cells() {
+        moves.push((cell, Red));
+        moves.push((cell, Blue));
+    }
+    return moves;
+}
+
+/// Scores every root move at the given depth from Order's point of view, or `None` if time ran out.
+fn score_moves(game_state: &GameState, moves: &[Move], depth: usize, deadline: Option<Instant>) -> Option<Vec<(Move, i32)>> {
+    let mut scored_moves = Vec::new();
+    for candidate in moves {
+        let mut next_state = *game_state;
+        let _ = next_state.play(candidate.0, candidate.1);
+        let score = search(&next_state, depth - 1, -WIN_SCORE * 2, WIN_SCORE * 2, deadline)?;
+        scored_moves.push((*candidate, score));
+    }
+    return Some(scored_moves);
+}
+
+fn search(game_state: &GameState, depth: usize, alpha: i32, beta: i32, deadline: Option<Instant>) -> Option<i32> {
+    if game_state.is_in_order() {
+        return Some(WIN_SCORE + depth as i32);
+    }
+    if !game_state.can_order_win() {
+        return Some(-WIN_SCORE - depth as i32);
+    }
+    if depth == 0 {
+        return Some(quick_score(game_state));
+    }
+    if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+        return None;
+    }
+
+    let mut alpha = alpha;
+    let mut beta = beta;
+    let order_to_move = Order.eq(&game_state.turn_player);
+    let mut best = if order_to_move { -WIN_SCORE * 2 } else { WIN_SCORE * 2 };
+    for candidate in ordered_moves(game_state, depth) {
+        let mut next_state = *game_state;
+        let _ = next_state.play(candidate.0, candidate.1);
+        let score = search(&next_state, depth - 1, alpha, beta, deadline)?;
+        if order_to_move {
+            best = best.max(score);
+            alpha = alpha.max(score);
+        } else {
+            best = best.min(score);
+            beta = beta.min(score);
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    return Some(best);
+}
+
+/// Orders moves best-first for the turn player so that alpha-beta can cut more of the tree.
+fn ordered_moves(game_state: &GameState, depth: usize) -> Vec<Move> {
+    let moves = legal_moves(game_state);
+    if depth < 2 {
+        return moves;
+    }
+    let mut scored: Vec<(Move, i32)> = moves.into_iter().map(|candidate| {
+        let mut next_state = *game_state;
+        let _ = next_state.play(candidate.0, candidate.1);
+        (candidate, quick_score(&next_state))
+    }).collect();
+    if Order.eq(&game_state.turn_player) {
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    } else {
+        scored.sort_by_key(|(_, score)| *score);
+    }
+    return scored.into_iter().map(|(candidate, _)| candidate).collect();
+}
+
+#[cfg(test)]
+mod engine_test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::engine::{Difficulty, Engine};
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_order_takes_the_win() {
+        let mut game_state = GameState::default_new();
+        game_state.play((2, 0), Red).expect("");
+        game_state.play((5, 5), Blue).expect("");
+        game_state.play((2, 1), Red).expect("");
+        game_state.play((2, 2), Red).expect("");
+        game_state.play((2, 3), Red).expect("");
+        game_state.play((0, 5), Blue).expect("");
+        let engine = Engine::new(Difficulty::Expert);
+        assert_eq!(engine.best_move(&game_state, &mut StdRng::seed_from_u64(7)), Some(((2, 4), Red)));
+    }
+
+    #[test]
+    fn test_chaos_blocks_the_win() {
+        let mut game_state = GameState::default_new();
+        game_state.play((2, 0), Red).expect("");
+        game_state.play((5, 5), Blue).expect("");
+        game_state.play((2, 1), Red).expect("");
+        game_state.play((2, 2), Red).expect("");
+        game_state.play((2, 3), Red).expect("");
+        let engine = Engine::new(Difficulty::Expert);
+        let chosen = engine.best_move(&game_state, &mut StdRng::seed_from_u64(7)).expect("");
+        let mut next_state = game_state;
+        next_state.play(chosen.0, chosen.1).expect("");
+        assert!(next_state.winning_moves().is_empty());
+    }
+
+    #[test]
+    fn test_difficulty_names() {
+        for difficulty in Difficulty::ALL {
+            assert_eq!(difficulty.name().parse::<Difficulty>(), Ok(difficulty));
+        }
+        assert!("grandmaster".parse::<Difficulty>().is_err());
+    }
+}
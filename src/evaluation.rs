@@ -0,0 +1,289 @@
+#![allow(clippy::needless_return)]
+
+use std::sync::OnceLock;
+use crate::players::Player::{Chaos, Order};
+use crate::state::{GameState, Line, Move, LINES, WINNING_LENGTH};
+use crate::tile::Tile;
+use crate::tile::Tile::{Blue, Empty, Red};
+
+pub const WIN_SCORE: i32 = 10_000;
+
+const LIVE_WINDOW_WEIGHT: i32 = 3;
+const OPEN_THREE_WEIGHT: i32 = 12;
+const OPEN_FOUR_WEIGHT: i32 = 60;
+const DOUBLE_THREAT_WEIGHT: i32 = 40;
+const OVERLINE_CELL_WEIGHT: i32 = 4;
+const BLOCKING_POTENTIAL_WEIGHT: i32 = 5;
+const ORDER_TO_MOVE_WITH_A_WIN: i32 = WIN_SCORE / 2;
+
+/// A static assessment of a position. Positive scores favour Order, negative scores favour Chaos.
+#[derive(Clone, Default)]
+pub struct Evaluation {
+    pub score: i32,
+    pub live_red_windows: usize,
+    pub live_blue_windows: usize,
+    pub open_fours: usize,
+    pub open_threes: usize,
+    pub double_threats: Vec<Move>,
+    pub overline_cells: Vec<Move>,
+    pub chaos_blocking_potential: usize,
+}
+
+/// Five consecutive cells of a line together with the cells directly before and after them.
+struct Window {
+    cells: [(usize, usize); WINNING_LENGTH],
+    neighbours: Vec<(usize, usize)>,
+}
+
+pub fn evaluate(game_state: &GameState) -> Evaluation {
+    let mut evaluation = Evaluation::default();
+    if game_state.is_in_order() {
+        evaluation.score = WIN_SCORE;
+        return evaluation;
+    }
+    if !game_state.can_order_win() {
+        evaluation.score = -WIN_SCORE;
+        return evaluation;
+    }
+
+    for window in windows() {
+        count_window(game_state, window, Red, &mut evaluation);
+        count_window(game_state, window, Blue, &mut evaluation);
+    }
+    evaluation.double_threats = double_threats(game_state);
+    evaluation.overline_cells = overline_cells(game_state);
+    evaluation.chaos_blocking_potential = chaos_blocking_potential(game_state);
+    evaluation.score = score(game_state, &evaluation);
+    return evaluation;
+}
+
+/// Number of windows in which `tile` could still complete exactly five in a row.
+pub fn live_windows(game_state: &GameState, tile: Tile) -> usize {
+    let mut count = 0;
+    for window in windows() {
+        if is_live(game_state, window, tile) {
+            count += 1;
+        }
+    }
+    return count;
+}
+
+fn score(game_state: &GameState, evaluation: &Evaluation) -> i32 {
+    let live_windows = (evaluation.live_red_windows + evaluation.live_blue_windows) as i32;
+    let mut score = live_windows * LIVE_WINDOW_WEIGHT
+        + evaluation.open_threes as i32 * OPEN_THREE_WEIGHT
+        + evaluation.open_fours as i32 * OPEN_FOUR_WEIGHT
+        + evaluation.double_threats.len() as i32 * DOUBLE_THREAT_WEIGHT
+        - evaluation.overline_cells.len() as i32 * OVERLINE_CELL_WEIGHT
+        - evaluation.chaos_blocking_potential as i32 * BLOCKING_POTENTIAL_WEIGHT;
+    if live_windows == 0 {
+        return -WIN_SCORE / 2;
+    }
+    if Order.eq(&game_state.turn_player) && evaluation.open_fours > 0 {
+        score += ORDER_TO_MOVE_WITH_A_WIN;
+    }
+    return score;
+}
+
+/// A cheaper score for use inside searches. It only counts windows and leaves out the move-by-move threat analysis.
+pub fn quick_score(game_state: &GameState) -> i32 {
+    if game_state.is_in_order() {
+        return WIN_SCORE;
+    }
+    if !game_state.can_order_win() {
+        return -WIN_SCORE;
+    }
+    let mut evaluation = Evaluation::default();
+    for window in windows() {
+        count_window(game_state, window, Red, &mut evaluation);
+        count_window(game_state, window, Blue, &mut evaluation);
+    }
+    return score(game_state, &evaluation);
+}
+
+fn windows() -> &'static Vec<Window> {
+    static WINDOWS: OnceLock<Vec<Window>> = OnceLock::new();
+    return WINDOWS.get_or_init(|| {
+        let mut windows = Vec::new();
+        for line in LINES {
+            for start in 0..=(line.length - WINNING_LENGTH) {
+                windows.push(window_of(&line, start));
+            }
+        }
+        windows
+    });
+}
+
+fn window_of(line: &Line, start: usize) -> Window {
+    let mut cells = [(0, 0); WINNING_LENGTH];
+    for (offset, cell) in cells.iter_mut().enumerate() {
+        *cell = line.cell(start + offset);
+    }
+    let mut neighbours = Vec::new();
+    if start > 0 {
+        neighbours.push(line.cell(start - 1));
+    }
+    if start + WINNING_LENGTH < line.length {
+        neighbours.push(line.cell(start + WINNING_LENGTH));
+    }
+    return Window { cells, neighbours };
+}
+
+/// A window is live for a colour if it holds no opposing pieces and filling it would not run into a sixth piece.
+fn is_live(game_state: &GameState, window: &Window, tile: Tile) -> bool {
+    for cell in window.cells {
+        let occupant = game_state.tile_at(cell);
+        if !Empty.eq(&occupant) && !tile.eq(&occupant) {
+            return false;
+        }
+    }
+    for neighbour in &window.neighbours {
+        if tile.eq(&game_state.tile_at(*neighbour)) {
+            return false;
+        }
+    }
+    return true;
+}
+
+fn count_window(game_state: &GameState, window: &Window, tile: Tile, evaluation: &mut Evaluation) {
+    if !is_live(game_state, window, tile) {
+        return;
+    }
+    match tile {
+        Red => evaluation.live_red_windows += 1,
+        Blue => evaluation.live_blue_windows += 1,
+        Empty => return
+    }
+    let pieces = window.cells.iter().filter(|cell| tile.eq(&game_state.tile_at(**cell))).count();
+    if pieces == WINNING_LENGTH - 1 {
+        evaluation.open_fours += 1;
+    } else if pieces == WINNING_LENGTH - 2 {
+        evaluation.open_threes += 1;
+    }
+}
+
+/// Moves after which Order would have two or more different ways to win.
+fn double_threats(game_state: &GameState) -> Vec<Move> {
+    let mut threats = Vec::new();
+    for cell in game_state.empty_cells() {
+        for tile in [Red, Blue] {
+            let mut next_state = *game_state;
+            let _ = next_state.play(cell, tile);
+            if next_state.is_in_order() {
+                continue;
+            }
+            let mut winning_cells: Vec<(usize, usize)> = next_state.winning_moves().iter().map(|(cell, _)| *cell).collect();
+            winning_cells.dedup();
+            if winning_cells.len() >= 2 {
+                threats.push((cell, tile));
+            }
+        }
+    }
+    return threats;
+}
+
+/// Moves that would complete six in a row, which can never count as a win.
+fn overline_cells(game_state: &GameState) -> Vec<Move> {
+    let mut cells = Vec::new();
+    for cell in game_state.empty_cells() {
+        for tile in [Red, Blue] {
+            let mut next_state = *game_state;
+            let _ = next_state.play(cell, tile);
+            if has_overline(&next_state, tile) {
+                cells.push((cell, tile));
+            }
+        }
+    }
+    return cells;
+}
+
+fn has_overline(game_state: &GameState, tile: Tile) -> bool {
+    for line in LINES {
+        if line.length > WINNING_LENGTH && (0..line.length).all(|index| tile.eq(&game_state.tile_at(line.cell(index)))) {
+            return true;
+        }
+    }
+    return false;
+}
+
+/// The largest number of live windows Chaos could destroy with a single move.
+fn chaos_blocking_potential(game_state: &GameState) -> usize {
+    let live_before = live_windows(game_state, Red) + live_windows(game_state, Blue);
+    let mut best = 0;
+    for cell in game_state.empty_cells() {
+        for tile in [Red, Blue] {
+            let mut next_state = *game_state;
+            let _ = next_state.play(cell, tile);
+            let live_after = live_windows(&next_state, Red) + live_windows(&next_state, Blue);
+            best = best.max(live_before - live_after);
+        }
+    }
+    if Chaos.eq(&game_state.turn_player) {
+        return best;
+    }
+    return best / 2;
+}
+
+#[cfg(test)]
+mod evaluation_test {
+    use crate::evaluation::{evaluate, live_windows, WIN_SCORE};
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_empty_board_windows() {
+        let game_state = GameState::default_new();
+        let evaluation = evaluate(&game_state);
+        assert_eq!(evaluation.live_red_windows, 32);
+        assert_eq!(evaluation.live_blue_windows, 32);
+        assert_eq!(evaluation.open_fours, 0);
+        assert!(evaluation.overline_cells.is_empty());
+    }
+
+    #[test]
+    fn test_open_four_is_a_winning_move() {
+        let mut game_state = GameState::default_new();
+        game_state.play((0, 1), Blue).expect("");
+        game_state.play((0, 2), Blue).expect("");
+        game_state.play((0, 3), Blue).expect("");
+        game_state.play((0, 4), Blue).expect("");
+        let evaluation = evaluate(&game_state);
+        assert!(evaluation.open_fours >= 1);
+        assert!(game_state.winning_moves().contains(&((0, 5), Blue)));
+        assert!(game_state.winning_moves().contains(&((0, 0), Blue)));
+        assert!(evaluation.score > 0);
+    }
+
+    #[test]
+    fn test_overline_cells() {
+        let mut game_state = GameState::default_new();
+        game_state.play((0, 0), Red).expect("");
+        game_state.play((0, 1), Red).expect("");
+        game_state.play((0, 2), Red).expect("");
+        game_state.play((0, 3), Red).expect("");
+        game_state.play((0, 5), Red).expect("");
+        let evaluation = evaluate(&game_state);
+        assert!(evaluation.overline_cells.contains(&((0, 4), Red)));
+        assert!(!game_state.winning_moves().contains(&((0, 4), Red)));
+    }
+
+    #[test]
+    fn test_mixed_window_is_dead() {
+        let mut game_state = GameState::default_new();
+        game_state.play((2, 2), Red).expect("");
+        game_state.play((2, 3), Blue).expect("");
+        assert!(live_windows(&game_state, Red) < 32);
+        assert!(live_windows(&game_state, Blue) < 32);
+    }
+
+    #[test]
+    fn test_finished_games() {
+        let mut game_state = GameState::default_new();
+        game_state.play((1, 1), Blue).expect("");
+        game_state.play((2, 2), Blue).expect("");
+        game_state.play((3, 3), Blue).expect("");
+        game_state.play((4, 4), Blue).expect("");
+        game_state.play((5, 5), Blue).expect("");
+        assert_eq!(evaluate(&game_state).score, WIN_SCORE);
+    }
+}
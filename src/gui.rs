@@ -1,52 +1,362 @@
-use eframe::epaint::{Vec2, Rounding};
+#![allow(clippy::needless_return)]
+
+use eframe::epaint::{Vec2, Rounding, Stroke};
 use eframe::{App, egui, Frame, NativeOptions, run_native};
 use eframe::egui::{Color32, Context, FontFamily, FontId, RichText, TextFormat, Ui, Button};
 use eframe::egui::text::LayoutJob;
+use eframe::egui::plot::{Line, Plot, PlotPoints};
 use egui::CentralPanel;
 use Screens::{End, Game};
 use Tile::Empty;
 use tile::Tile::{Blue, Red};
-use crate::gui::Screens::Welcome;
+use crate::gui::Screens::{Puzzles, Replaying, Settings, Welcome};
 use crate::players::Player;
 use crate::players::Player::{Chaos, Order};
-use crate::state::GameState;
-use crate::{config, make_a_random_move, tile};
+use crate::state::{GameState, Threats};
+use crate::evaluation::evaluate;
+use crate::tile;
+use crate::agent::{Agent, computer_agent, GuiHuman, PendingMove, Turn};
+use crate::config::Options;
+use crate::display::Renderer;
+use crate::locale::{Language, Segment, RULES, WELCOME};
+use crate::theme::{Theme, ThemeColor};
+use crate::preferences::save_window_preferences;
+use crate::engine::Difficulty;
+use crate::state::Move;
+use crate::record::GameRecord;
+use crate::analysis::{analyse_game, Analysis};
+use crate::json::export_json;
+use crate::diagram::Diagram;
+use crate::replay::Replay;
+use crate::clock::Clocks;
+use crate::matches::{home_side, Match};
+use crate::network::NetworkSession;
+use crate::puzzle::{load_puzzles, save_puzzles, success_rate, Puzzle};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 
 use crate::tile::Tile;
 
 
+/// An agent for one side, with the channel for its clicks if a person plays it.
+type SideAgent = (Box<dyn Agent>, Option<Sender<Move>>);
+
 pub struct MainWindow {
     game_state: GameState,
     chosen_tile: Tile,
     screen: Screens,
     winner: Option<Player>,
-    tile_size: f32,
+    renderer: Renderer,
+    forced_cells: Vec<(usize, usize)>,
+    options: Options,
+    /// The options the window opened with, to tell which settings the player changed.
+    started: Options,
+    order_agent: Option<Box<dyn Agent>>,
+    chaos_agent: Option<Box<dyn Agent>>,
+    pending_move: Option<PendingMove>,
+    order_clicks: Option<Sender<Move>>,
+    chaos_clicks: Option<Sender<Move>>,
+    record: GameRecord,
+    puzzles: Vec<Puzzle>,
+    puzzle_index: usize,
+    puzzle_answer: Option<bool>,
+    analysis: Option<Analysis>,
+    pending_analysis: Option<JoinHandle<Result<Analysis, &'static str>>>,
+    replay: Option<Replay>,
+    replay_playing: bool,
+    replay_speed: u64,
+    replay_step: Instant,
+    clocks: Option<Clocks>,
+    series: Option<Match>,
+    network: Option<NetworkSession>,
+    network_error: Option<&'static str>,
+    pending_rematch: Option<JoinHandle<Result<bool, &'static str>>>,
+    /// What became of the last JSON export on the end screen.
+    export_status: Option<String>,
+    /// Why the game was abandoned, if an agent failed to move.
+    agent_error: Option<String>,
+    /// The score of the last position evaluated, so repaints between moves do not evaluate it again.
+    evaluation: Option<(GameState, i32)>,
+    /// The threats in the last position looked at, for the same reason.
+    threats: Option<(GameState, Threats)>,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum Screens {
     Welcome,
+    Settings,
     Game,
+    Puzzles,
+    Replaying,
     End,
 }
 
 impl MainWindow {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self::default()
+    pub fn new(_cc: &eframe::CreationContext<'_>, options: Options) -> Self {
+        Self::with_options(options)
     }
 
-    fn default() -> Self {
+    fn with_options(options: Options) -> Self {
         Self {
             game_state: GameState::default_new(),
             chosen_tile: Blue,
             screen: Welcome,
             winner: None,
-            tile_size: 110.0,
+            renderer: Renderer::from_options(&options),
+            forced_cells: Vec::new(),
+            options: options.clone(),
+            started: options.clone(),
+            order_agent: None,
+            chaos_agent: None,
+            pending_move: None,
+            order_clicks: None,
+            chaos_clicks: None,
+            record: GameRecord::default(),
+            puzzles: load_trainer_puzzles(&options),
+            puzzle_index: 0,
+            puzzle_answer: None,
+            analysis: None,
+            pending_analysis: None,
+            replay: load_replay(&options),
+            replay_playing: false,
+            replay_speed: options.replay_speed,
+            replay_step: Instant::now(),
+            clocks: None,
+            series: None,
+            network: None,
+            network_error: None,
+            pending_rematch: None,
+            export_status: None,
+            agent_error: None,
+            evaluation: None,
+            threats: None,
+        }
+    }
+
+    /// Pairs an agent with each side: the computer players the options ask for, and people clicking on the board for the rest.
+    /// In a match the participants swap sides after every game.
+    fn start_game(&mut self) {
+        let mut options = self.options.clone();
+        let (order, chaos) = if self.options.connect.is_some() {
+            match self.network_agents() {
+                Ok(v) => v,
+                Err(v) => return self.network_error = Some(v)
+            }
+        } else {
+            self.match_agents(&mut options)
+        };
+        let ((order_agent, order_clicks), (chaos_agent, chaos_clicks)) = (order, chaos);
+        self.record = match &self.series {
+            Some(series) => series.next_record(options.seed()),
+            None => GameRecord::new(&order_agent.name(), &chaos_agent.name(), options.seed())
+        };
+        self.order_agent = Some(order_agent);
+        self.chaos_agent = Some(chaos_agent);
+        self.game_state = self.network.as_ref().map(|session| session.start).unwrap_or_else(GameState::default_new);
+        self.winner = None;
+        self.pending_move = None;
+        self.analysis = None;
+        self.pending_analysis = None;
+        self.export_status = None;
+        self.agent_error = None;
+        self.clocks = self.options.time_control.map(Clocks::new);
+        if let Some(clocks) = &self.clocks {
+            self.record.set_tag("TimeControl", &clocks.control.to_string());
+        }
+        self.order_clicks = order_clicks;
+        self.chaos_clicks = chaos_clicks;
+        self.screen = Game;
+    }
+
+    /// Connects to the host given to the `connect` command on the first game. The player on the other end gets a network agent.
+    fn network_agents(&mut self) -> Result<(SideAgent, SideAgent), &'static str> {
+        if self.network.is_none() {
+            self.network = Some(NetworkSession::connect(self.options.connect.as_ref().unwrap())?);
+        }
+        let session = self.network.as_ref().unwrap();
+        let local = gui_agent(&self.options, session.local);
+        let remote: SideAgent = (Box::new(session.remote_agent()), None);
+        match session.local {
+            Order => return Ok((local, remote)),
+            Chaos => return Ok((remote, local))
+        }
+    }
+
+    /// The agents for Order and Chaos, and the options for this game of the match, if one is being played.
+    fn match_agents(&mut self, options: &mut Options) -> (SideAgent, SideAgent) {
+        let mut participants = [gui_agent(&self.options, home_side(0)), gui_agent(&self.options, home_side(1))];
+        if self.series.is_none() {
+            if let Some(pairs) = self.options.match_pairs {
+                self.series = Some(Match::resume_or_new(self.options.match_file.as_ref(), &participants[0].0.name(), &participants[1].0.name(), pairs));
+            }
+        }
+        let mut order_participant = 0;
+        if let Some(series) = &self.series {
+            *options = series.game_options(&self.options);
+            order_participant = series.participant(series.records.len(), Order);
+            if !series.records.is_empty() {
+                participants = [gui_agent(options, home_side(0)), gui_agent(options, home_side(1))];
+            }
+        }
+        let [first, second] = participants;
+        if order_participant == 0 {
+            return (first, second);
+        }
+        return (second, first);
+    }
+
+    fn start_puzzle(&mut self, index: usize) {
+        self.puzzle_index = index % self.puzzles.len();
+        self.puzzle_answer = None;
+        self.export_status = None;
+        self.game_state = self.puzzles[self.puzzle_index].position;
+        self.screen = Puzzles;
+    }
+
+    /// Checks a clicked move against the current puzzle and saves the attempt back to the puzzle file.
+    fn answer_puzzle(&mut self, coordinates: (usize, usize)) {
+        if self.puzzle_answer.is_some() || !Empty.eq(&self.game_state.tile_at(coordinates)) {
+            return;
+        }
+        let correct = self.puzzles[self.puzzle_index].answer((coordinates, self.chosen_tile));
+        self.puzzle_answer = Some(correct);
+        if let Some(path) = &self.options.puzzles {
+            if let Err(v) = save_puzzles(&self.puzzles, path) {
+                self.export_status = Some(self.renderer.language.text(v).to_string());
+            }
+        }
+    }
+
+    fn clicks(&self) -> Option<&Sender<Move>> {
+        match self.game_state.turn_player {
+            Order => self.order_clicks.as_ref(),
+            Chaos => self.chaos_clicks.as_ref()
+        }
+    }
+
+    /// Asks the turn player's agent for a move on a background thread and plays it once it arrives.
+    fn advance_game(&mut self, ctx: &Context) {
+        if self.winner.is_some() {
+            return;
+        }
+        if let Some(loser) = self.clocks.as_ref().and_then(|clocks| clocks.flagged()) {
+            return self.end_on_time(loser);
+        }
+        let turn_player = self.game_state.turn_player;
+        match self.pending_move.take() {
+            None => {
+                let agent = match turn_player {
+                    Order => self.order_agent.take(),
+                    Chaos => self.chaos_agent.take()
+                };
+                if let Some(agent) = agent {
+                    let time_limit = self.clocks.as_ref().map(|clocks| clocks.move_budget(turn_player));
+                    if let Some(clocks) = self.clocks.as_mut() {
+                        clocks.start(turn_player);
+                    }
+                    self.pending_move = Some(PendingMove::start(agent, self.game_state, time_limit));
+                }
+            }
+            Some(pending_move) if pending_move.is_finished() => {
+                if let Some(Err(loser)) = self.clocks.as_mut().map(|clocks| clocks.stop()) {
+                    return self.end_on_time(loser);
+                }
+                let (agent, result) = pending_move.finish();
+                let agent = agent.unwrap_or_else(|| {
+                    let (agent, clicks) = GuiHuman::new();
+                    match turn_player {
+                        Order => self.order_clicks = Some(clicks),
+                        Chaos => self.chaos_clicks = Some(clicks)
+                    }
+                    Box::new(agent)
+                });
+                match turn_player {
+                    Order => self.order_agent = Some(agent),
+                    Chaos => self.chaos_agent = Some(agent)
+                }
+                match result {
+                    Ok(Turn::Move((coordinates, tile))) => {
+                        if self.game_state.play(coordinates, tile).is_ok() {
+                            self.record.moves.push((coordinates, tile));
+                        }
+                        evaluate_game_state(&self.game_state, &mut self.winner);
+                        if let Some(winner) = self.winner {
+                            self.record.set_winner(winner);
+                            self.finish_game(None);
+                        }
+                    }
+                    Ok(Turn::Resign) => self.resign(turn_player),
+                    Ok(Turn::LoadPosition(_)) => self.abandon(turn_player, "Positions cannot be loaded in the window"),
+                    Err(v) => self.abandon(turn_player, v)
+                }
+            }
+            Some(pending_move) => self.pending_move = Some(pending_move)
+        }
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
+
+    /// Ends the game in favour of the opponent of the player whose clock ran out. A move still being chosen is abandoned.
+    fn end_on_time(&mut self, loser: Player) {
+        self.pending_move = None;
+        self.record.set_time_forfeit(loser);
+        self.winner = Some(loser.opponent());
+        self.finish_game(None);
+    }
+
+    /// Ends the game in favour of the opponent of the player whose agent could not move, keeping the reason for the end screen.
+    fn abandon(&mut self, loser: Player, message: &'static str) {
+        let language = self.renderer.language;
+        let agent = match loser {
+            Order => &self.order_agent,
+            Chaos => &self.chaos_agent
+        };
+        let name = agent.as_ref().map(|agent| agent.name()).unwrap_or_default();
+        self.agent_error = Some(language.format("{} failed to move. Message: {}", &[&name, &language.text(message)]));
+        self.pending_move = None;
+        self.record.set_abandonment(loser);
+        self.winner = Some(loser.opponent());
+        self.finish_game(None);
+    }
+
+    fn resign(&mut self, loser: Player) {
+        self.pending_move = None;
+        self.record.set_resignation(loser);
+        self.winner = Some(loser.opponent());
+        self.finish_game(Some(loser));
+    }
+
+    /// Tells the agents the game is over, saves its record and adds it to the match, if one is being played.
+    /// The first file that could not be written is reported on the end screen.
+    fn finish_game(&mut self, resigned: Option<Player>) {
+        for agent in [self.order_agent.as_mut(), self.chaos_agent.as_mut()].into_iter().flatten() {
+            agent.game_over(&self.game_state, resigned);
+        }
+        let mut saved = Ok(());
+        if let Some(path) = &self.options.save {
+            saved = saved.and(self.record.save(path));
+        }
+        if let Some(path) = &self.options.export_json {
+            saved = saved.and(export_json(&self.record, path));
+        }
+        if let Some(series) = self.series.as_mut() {
+            series.add_game(self.record.clone());
+            if let Some(path) = &self.options.match_file {
+                saved = saved.and(series.save(path));
+            }
+        }
+        if let Err(v) = saved {
+            self.export_status = Some(self.renderer.language.text(v).to_string());
         }
     }
 
     fn show_grid(&mut self, ui: &mut Ui, interactive: bool) {
+        self.forced_cells = Vec::new();
+        if self.screen != Puzzles && self.winner.is_none() && !self.game_state.is_in_order() {
+            self.forced_cells = self.position_threats().forced_cells.clone();
+        }
         egui::Grid::new("Demo Grid").spacing(Vec2::new(0.0, 0.0)).show(ui, |ui| {
             let original_board;
             {
@@ -62,58 +372,411 @@ impl MainWindow {
     }
 
     fn show_pawn_selector(&mut self, ui: &mut Ui, tile_size: f32) {
-        ui.label("\n\nSelect Pawn:");
+        ui.label(format!("\n\n{}", self.renderer.language.text("Select Pawn:")));
         egui::Grid::new("Demo Grid2").show(ui, |ui| {
-            ui.selectable_value(&mut self.chosen_tile, Blue, RichText::new("🌑").color(Color32::BLUE).size(tile_size));
-            ui.selectable_value(&mut self.chosen_tile, Red, RichText::new("❌").color(Color32::RED).size(tile_size));
+            ui.selectable_value(&mut self.chosen_tile, Blue, RichText::new("🌑").color(theme_color(self.renderer.theme.o)).size(tile_size));
+            ui.selectable_value(&mut self.chosen_tile, Red, RichText::new("❌").color(theme_color(self.renderer.theme.x)).size(tile_size));
             ui.end_row();
         });
     }
 
+    fn show_threats(&mut self, ui: &mut Ui) {
+        if self.forced_cells.is_empty() {
+            return;
+        }
+        let language = self.renderer.language;
+        let cells: Vec<String> = self.forced_cells.iter().map(|cell| language.coordinates(cell)).collect();
+        let neutralisable = self.position_threats().can_chaos_neutralise_all();
+        let text = match self.game_state.turn_player {
+            Order => language.format("{} can win right now at {}", &[&language.player(&Order), &cells.join(", ")]),
+            Chaos if neutralisable => language.format("{} must neutralise {}", &[&language.player(&Chaos), &cells.join(", ")]),
+            Chaos => language.format("{} cannot stop every threat at {}", &[&language.player(&Chaos), &cells.join(", ")])
+        };
+        ui.label(RichText::new(text).color(theme_color(self.renderer.theme.highlight)).size(16.0));
+    }
+
     fn show_welcome_screen(&mut self, ctx: &Context) {
+        let language = self.renderer.language;
         CentralPanel::default().show(ctx, |ui| {
-            ui.label(welcome_screen_layout());
+            ui.label(welcome_screen_layout(&self.renderer));
             ui.add_space(64.0);
-            if ui.add(egui::Button::new(RichText::new(" Continue ").size(32.0))).clicked() {
-                self.screen = Game;
+            if let Some(error) = self.network_error {
+                ui.label(RichText::new(language.format("Could not join the game: {}", &[&language.text(error)])).color(theme_color(self.renderer.theme.highlight)).size(24.0));
             }
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new(button_text(language, "Continue", 32.0))).clicked() {
+                    self.start_game();
+                }
+                if ui.add(egui::Button::new(button_text(language, "Settings", 32.0))).clicked() {
+                    self.screen = Settings;
+                }
+                if self.replay.is_some() && ui.add(egui::Button::new(button_text(language, "Replay", 32.0))).clicked() {
+                    self.screen = Replaying;
+                }
+                if !self.puzzles.is_empty() && ui.add(egui::Button::new(button_text(language, "Puzzles", 32.0))).clicked() {
+                    self.start_puzzle(0);
+                }
+            });
         });
     }
 
+    fn show_settings_screen(&mut self, ctx: &Context) {
+        let language = self.renderer.language;
+        CentralPanel::default().show(ctx, |ui| {
+            ui.heading(language.text("Settings"));
+            ui.add_space(32.0);
+            ui.label(RichText::new(language.text("Computer Opponent:")).size(32.0));
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.options.computer, None, RichText::new(language.text("Nobody")).size(24.0));
+                ui.radio_value(&mut self.options.computer, Some(Order), player_fmt(&self.renderer, &Order).size(24.0));
+                ui.radio_value(&mut self.options.computer, Some(Chaos), player_fmt(&self.renderer, &Chaos).size(24.0));
+            });
+            ui.add_space(32.0);
+            ui.label(RichText::new(language.text("Difficulty:")).size(32.0));
+            ui.horizontal(|ui| {
+                for difficulty in Difficulty::ALL {
+                    ui.radio_value(&mut self.options.difficulty, difficulty, RichText::new(language.text(difficulty.name())).size(24.0));
+                }
+            });
+            ui.add_space(64.0);
+            if ui.add(egui::Button::new(button_text(language, "Back", 32.0))).clicked() {
+                self.screen = Welcome;
+            }
+        });
+    }
+
+    /// The evaluation of the position on the board, worked out again only once the position changes.
+    fn evaluation_score(&mut self) -> i32 {
+        match self.evaluation {
+            Some((position, score)) if position == self.game_state => return score,
+            _ => {
+                let score = evaluate(&self.game_state).score;
+                self.evaluation = Some((self.game_state, score));
+                return score;
+            }
+        }
+    }
+
+    /// The threats in the position on the board, worked out again only once the position changes.
+    fn position_threats(&mut self) -> &Threats {
+        if !self.threats.as_ref().is_some_and(|(position, _)| *position == self.game_state) {
+            self.threats = Some((self.game_state, self.game_state.threats()));
+        }
+        return &self.threats.as_ref().unwrap().1;
+    }
+
     fn show_game_screen(&mut self, ctx: &Context) {
+        let language = self.renderer.language;
+        let score = self.evaluation_score();
         CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Order & Chaos");
+            ui.heading(format!("{} & {}", language.player(&Order), language.player(&Chaos)));
             egui::Grid::new("Demo Grid3").show(ui, |ui| {
-                ui.label(RichText::new("Current Active Player:").size(32.0));
-                ui.label(player_fmt(&self.game_state.turn_player));
+                ui.label(RichText::new(language.text("Current Active Player:")).size(32.0));
+                ui.label(player_fmt(&self.renderer, &self.game_state.turn_player));
+                ui.end_row();
+                if let Some(clocks) = &self.clocks {
+                    ui.label(RichText::new(language.text("Clocks:")).size(32.0));
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(format!("{} {}", language.player(&Order), clocks.clock_fmt(Order))).color(get_player_color(&self.renderer.theme, Order)).size(32.0));
+                        ui.label(RichText::new(format!("{} {}", language.player(&Chaos), clocks.clock_fmt(Chaos))).color(get_player_color(&self.renderer.theme, Chaos)).size(32.0));
+                    });
+                    ui.end_row();
+                }
+                ui.label(RichText::new(language.text("Evaluation:")).size(16.0));
+                ui.label(RichText::new(format!("{:+}", score)).size(16.0));
+                ui.end_row();
+                ui.label(RichText::new(language.text("Seed:")).size(16.0));
+                ui.label(RichText::new(self.options.seed().to_string()).size(16.0));
                 ui.end_row();
                 egui::Grid::new("Demo Grid4").show(ui, |ui| {
-                    ui.label(RichText::new("Tile Size:").size(16.0));
-                    ui.add(egui::Slider::new(&mut self.tile_size, 5.0..=250.0));
+                    ui.label(RichText::new(language.text("Tile Size:")).size(16.0));
+                    ui.add(egui::Slider::new(&mut self.renderer.tile_size, 5.0..=250.0));
                 });
             });
-            self.show_grid(ui, !config::get().ai_vs_ai_demo);
-            self.show_pawn_selector(ui, self.tile_size);
+            let interactive = self.clicks().is_some();
+            self.show_grid(ui, interactive);
+            self.show_threats(ui);
+            self.show_pawn_selector(ui, self.renderer.tile_size);
+            ui.horizontal(|ui| {
+                if interactive && self.winner.is_none() && ui.add(egui::Button::new(button_text(language, "Resign", 24.0))).clicked() {
+                    self.resign(self.game_state.turn_player);
+                }
+                if ui.add(egui::Button::new(button_text(language, "Save diagram", 24.0))).clicked() {
+                    self.save_diagram();
+                }
+            });
+            if let Some(status) = &self.export_status {
+                ui.label(RichText::new(status).size(16.0));
+            }
+        });
+
+        self.advance_game(ctx);
+    }
+
+    fn show_puzzle_screen(&mut self, ctx: &Context) {
+        let language = self.renderer.language;
+        CentralPanel::default().show(ctx, |ui| {
+            let puzzle = self.puzzles[self.puzzle_index];
+            ui.heading(language.format("Puzzle {} of {}", &[&(self.puzzle_index + 1), &self.puzzles.len()]));
+            ui.label(RichText::new(self.renderer.puzzle_kind(&puzzle.kind)).size(32.0));
+            ui.label(RichText::new(language.format("Success rate: {}%", &[&success_rate(&self.puzzles)])).size(16.0));
+            self.show_grid(ui, self.puzzle_answer.is_none());
+            match self.puzzle_answer {
+                Some(true) => ui.label(RichText::new(language.text("Correct!")).size(24.0)),
+                Some(false) => ui.label(RichText::new(language.format("Not quite. The answer was {}", &[&language.move_text(&puzzle.solution)])).color(theme_color(self.renderer.theme.highlight)).size(24.0)),
+                None => ui.label(RichText::new(language.text("Click the cell to play the selected pawn on.")).size(24.0))
+            };
+            if let Some(status) = &self.export_status {
+                ui.label(RichText::new(status).size(16.0));
+            }
+            self.show_pawn_selector(ui, self.renderer.tile_size);
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new(button_text(language, "Next Puzzle", 32.0))).clicked() {
+                    self.start_puzzle(self.puzzle_index + 1);
+                }
+                if ui.add(egui::Button::new(button_text(language, "Back", 32.0))).clicked() {
+                    self.game_state = GameState::default_new();
+                    self.screen = Welcome;
+                }
+            });
         });
+    }
 
-        if config::get().ai_vs_ai_demo {
-            make_a_random_move(&mut self.game_state);
-            evaluate_game_state(&self.game_state, &mut self.winner);
+    fn show_replay_screen(&mut self, ctx: &Context) {
+        let mut replay = match self.replay.take() {
+            Some(v) => v,
+            None => return
+        };
+        if self.replay_playing && self.replay_step.elapsed() >= Duration::from_millis(self.replay_speed) {
+            replay.forward();
+            self.replay_step = Instant::now();
+            self.replay_playing = !replay.is_at_end();
+        }
+        self.game_state = *replay.game_state();
+        let language = self.renderer.language;
+        CentralPanel::default().show(ctx, |ui| {
+            ui.heading(language.text("Replay"));
+            ui.label(RichText::new(language.format("{} vs {}", &[&replay.record.tag("Order").unwrap_or("?"), &replay.record.tag("Chaos").unwrap_or("?")])).size(24.0));
+            ui.label(RichText::new(language.format("Move {} of {}", &[&replay.position(), &replay.len()])).size(24.0));
+            if let Some(last_move) = replay.last_move() {
+                ui.label(RichText::new(language.format("Last move: {}", &[&language.move_text(&last_move)])).size(16.0));
+            }
+            self.show_grid(ui, false);
+            ui.add_space(16.0);
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new(RichText::new(" |< ").size(32.0))).clicked() {
+                    replay.jump_to_start();
+                }
+                if ui.add(egui::Button::new(RichText::new(" < ").size(32.0))).clicked() {
+                    replay.back();
+                }
+                let play_text = if self.replay_playing { language.text("Pause") } else { language.text("Play") };
+                if ui.add(egui::Button::new(RichText::new(format!(" {} ", play_text)).size(32.0))).clicked() {
+                    self.replay_playing = !self.replay_playing && !replay.is_at_end();
+                    self.replay_step = Instant::now();
+                }
+                if ui.add(egui::Button::new(RichText::new(" > ").size(32.0))).clicked() {
+                    replay.forward();
+                }
+                if ui.add(egui::Button::new(RichText::new(" >| ").size(32.0))).clicked() {
+                    replay.jump_to_end();
+                }
+            });
+            egui::Grid::new("Replay Speed").show(ui, |ui| {
+                ui.label(RichText::new(language.text("Milliseconds per move:")).size(16.0));
+                ui.add(egui::Slider::new(&mut self.replay_speed, 100..=5000));
+            });
+            ui.add_space(32.0);
+            if ui.add(egui::Button::new(button_text(language, "Back", 32.0))).clicked() {
+                self.replay_playing = false;
+                self.game_state = GameState::default_new();
+                self.screen = Welcome;
+            }
+        });
+        self.replay = Some(replay);
+        if self.replay_playing {
+            ctx.request_repaint_after(Duration::from_millis(50));
         }
     }
+
     fn show_end_screen(&mut self, ctx: &Context, frame: &mut Frame) {
+        let language = self.renderer.language;
         CentralPanel::default().show(ctx, |ui| {
             ui.label(end_screen_layout(self));
-            self.show_grid(ui, false);
-            ui.add_space(32.0);
-            if ui.add(egui::Button::new(RichText::new(" Exit Game ").size(32.0))).clicked() {
-                frame.close();
+            if let Some(error) = &self.agent_error {
+                ui.label(RichText::new(error).color(theme_color(self.renderer.theme.highlight)).size(24.0));
+            }
+            ui.horizontal_top(|ui| {
+                ui.vertical(|ui| {
+                    self.show_grid(ui, false);
+                    ui.add_space(32.0);
+                    self.show_match(ui);
+                    self.show_rematch(ui);
+                    ui.horizontal(|ui| {
+                        if self.analysis.is_none() && self.pending_analysis.is_none() && ui.add(egui::Button::new(button_text(language, "Analyse", 32.0))).clicked() {
+                            self.start_analysis();
+                        }
+                        if ui.add(egui::Button::new(button_text(language, "Export", 32.0))).clicked() {
+                            self.export_game();
+                        }
+                        if ui.add(egui::Button::new(button_text(language, "Save diagram", 32.0))).clicked() {
+                            self.save_diagram();
+                        }
+                        if ui.add(egui::Button::new(button_text(language, "Exit Game", 32.0))).clicked() {
+                            frame.close();
+                        }
+                    });
+                    if let Some(status) = &self.export_status {
+                        ui.label(RichText::new(status).size(16.0));
+                    }
+                });
+                ui.vertical(|ui| self.show_analysis(ui));
+            });
+        });
+        if self.pending_analysis.is_some() {
+            self.finish_analysis();
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    fn show_match(&mut self, ui: &mut Ui) {
+        let language = self.renderer.language;
+        let series = match &self.series {
+            Some(v) => v,
+            None => return
+        };
+        ui.label(RichText::new(series.score_table(language)).monospace().size(16.0));
+        if series.is_over() {
+            ui.label(RichText::new(series.summary(language)).size(24.0));
+        } else if ui.add(egui::Button::new(button_text(language, "Next Game", 32.0))).clicked() {
+            self.start_game();
+        }
+        ui.add_space(16.0);
+    }
+
+    fn show_rematch(&mut self, ui: &mut Ui) {
+        let language = self.renderer.language;
+        let session = match &self.network {
+            Some(v) => v.clone(),
+            None => {
+                if self.options.connect.is_none() {
+                    if let Some(error) = self.network_error {
+                        ui.label(RichText::new(language.text(error)).size(24.0));
+                    }
+                }
+                return;
+            }
+        };
+        match self.pending_rematch.take() {
+            None => {
+                if ui.add(egui::Button::new(button_text(language, "Rematch", 32.0))).clicked() {
+                    self.pending_rematch = Some(thread::spawn(move || session.rematch(true)));
+                }
+            }
+            Some(handle) if handle.is_finished() => {
+                if let Ok(Ok(true)) = handle.join() {
+                    if let Some(session) = self.network.as_mut() {
+                        session.swap_sides();
+                    }
+                    return self.start_game();
+                }
+                self.network = None;
+                self.options.connect = None;
+                self.network_error = Some("The other player left.");
+            }
+            Some(handle) => {
+                ui.label(RichText::new(language.text("Waiting for the other player...")).size(24.0));
+                ui.ctx().request_repaint_after(Duration::from_millis(100));
+                self.pending_rematch = Some(handle);
+            }
+        }
+        ui.add_space(16.0);
+    }
+
+    /// Writes the game as JSON to the file given with --export-json, or to game.json.
+    fn export_game(&mut self) {
+        let path = self.options.export_json.clone().unwrap_or_else(|| "game.json".to_string());
+        match export_json(&self.record, &path) {
+            Ok(_) => self.export_status = Some(self.renderer.language.format("Exported to {}", &[&path])),
+            Err(v) => self.export_status = Some(self.renderer.language.text(v).to_string())
+        }
+    }
+
+    /// Draws the board with the last move marked to the file given with --diagram, or to diagram.png.
+    fn save_diagram(&mut self) {
+        let path = self.options.diagram.clone().unwrap_or_else(|| "diagram.png".to_string());
+        let mut diagram = Diagram::new(self.game_state);
+        diagram.last_move = self.record.moves.last().map(|(coordinates, _)| *coordinates);
+        match diagram.save(&path) {
+            Ok(_) => self.export_status = Some(self.renderer.language.format("Saved the diagram to {}", &[&path])),
+            Err(v) => self.export_status = Some(self.renderer.language.text(v).to_string())
+        }
+    }
+
+    fn start_analysis(&mut self) {
+        let record = self.record.clone();
+        let time_per_move = Duration::from_millis(self.options.analysis_time);
+        self.pending_analysis = Some(thread::spawn(move || analyse_game(&record, time_per_move)));
+    }
+
+    fn finish_analysis(&mut self) {
+        match self.pending_analysis.take() {
+            Some(handle) if handle.is_finished() => {
+                if let Ok(Ok(analysis)) = handle.join() {
+                    self.analysis = Some(analysis);
+                }
+            }
+            pending_analysis => self.pending_analysis = pending_analysis
+        }
+    }
+
+    /// The evaluation after every move as a graph, followed by the moves with their annotations.
+    fn show_analysis(&mut self, ui: &mut Ui) {
+        let language = self.renderer.language;
+        if self.pending_analysis.is_some() {
+            ui.label(RichText::new(language.text("Analysing the game...")).size(24.0));
+            return;
+        }
+        let analysis = match &self.analysis {
+            Some(v) => v,
+            None => return
+        };
+        let points: PlotPoints = analysis.reviews.iter().enumerate().map(|(index, review)| [(index + 1) as f64, review.score as f64]).collect();
+        Plot::new("Evaluation Graph").height(200.0).width(500.0).show(ui, |plot_ui| plot_ui.line(Line::new(points).name(language.text("Evaluation"))));
+        if let Some(index) = analysis.turning_point {
+            ui.label(RichText::new(language.format("Turning point: move {}", &[&(index + 1)])).size(16.0));
+        }
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            for (index, review) in analysis.reviews.iter().enumerate() {
+                let mut text = format!("{}. {} {} {:+}", index + 1, language.player(&review.player), language.move_text(&review.played_move), review.score);
+                if let Some(annotation) = review.annotation {
+                    text.push_str("  ");
+                    text.push_str(&language.format("{}, best was {}", &[&language.text(annotation.name()), &language.move_text(&review.best_move)]));
+                }
+                let color = if review.annotation.is_some() { theme_color(self.renderer.theme.highlight) } else { Color32::GRAY };
+                ui.label(RichText::new(text).color(color).size(16.0));
             }
         });
+        let path = self.options.analysis_file.clone().unwrap_or_else(|| "analysis.txt".to_string());
+        if ui.add(egui::Button::new(RichText::new(format!(" {} ", language.format("Export to {}", &[&path]))).size(16.0))).clicked() {
+            self.export_status = match analysis.save(&self.record, &path) {
+                Ok(_) => Some(language.format("Exported to {}", &[&path])),
+                Err(v) => Some(language.text(v).to_string())
+            };
+        }
     }
 }
 
 impl App for MainWindow {
+    fn on_close_event(&mut self) -> bool {
+        let mut options = self.options.clone();
+        options.tile_size = self.renderer.tile_size;
+        if let Err(v) = save_window_preferences(&self.started, &options) {
+            println!("{}", v);
+        }
+        return true;
+    }
+
     fn update(&mut self, context: &Context, frame: &mut Frame) {
         if self.winner.is_some() {
             self.screen = End;
@@ -121,15 +784,41 @@ impl App for MainWindow {
 
         match self.screen {
             Welcome => self.show_welcome_screen(context),
+            Settings => self.show_settings_screen(context),
             Game => self.show_game_screen(context),
+            Puzzles => self.show_puzzle_screen(context),
+            Replaying => self.show_replay_screen(context),
             End => self.show_end_screen(context, frame)
         }
     }
 }
 
-pub fn show_main_screen() {
+fn load_replay(options: &Options) -> Option<Replay> {
+    match &options.replay {
+        Some(path) => return Replay::load(path).ok(),
+        None => return None
+    }
+}
+
+fn load_trainer_puzzles(options: &Options) -> Vec<Puzzle> {
+    match &options.puzzles {
+        Some(path) => return load_puzzles(path).unwrap_or_default(),
+        None => return Vec::new()
+    }
+}
+
+fn gui_agent(options: &Options, player: Player) -> (Box<dyn Agent>, Option<Sender<Move>>) {
+    if let Some(agent) = computer_agent(options, player) {
+        return (agent, None);
+    }
+    let (agent, clicks) = GuiHuman::new();
+    return (Box::new(agent), Some(clicks));
+}
+
+pub fn show_main_screen(options: Options) {
     let native_options = NativeOptions { maximized: true, ..Default::default() };
-    run_native("Order & Chaos", native_options, Box::new(|cc| Box::new(MainWindow::new(cc)))).unwrap();
+    let title = format!("{} & {}", options.language.player(&Order), options.language.player(&Chaos));
+    run_native(&title, native_options, Box::new(|cc| Box::new(MainWindow::new(cc, options)))).unwrap();
 }
 
 
@@ -142,32 +831,41 @@ fn show_tile(main_window: &mut MainWindow, tile: &Tile, ui: &mut Ui, coordinates
 }
 
 fn add_empty(main_window: &mut MainWindow,ui: &mut Ui, coordinates: (usize, usize), interactive: bool) {
-    add_button(main_window, ui, coordinates, interactive, "⬛".to_owned(), Color32::WHITE);
+    add_button(main_window, ui, coordinates, interactive, "⬛".to_owned(), theme_color(main_window.renderer.theme.board));
 }
 
 fn add_blue(main_window: &mut MainWindow,ui: &mut Ui, coordinates: (usize, usize), interactive: bool) {
-    add_button(main_window, ui, coordinates, interactive, "🌑".to_owned(), Color32::BLUE);
+    add_button(main_window, ui, coordinates, interactive, "🌑".to_owned(), theme_color(main_window.renderer.theme.o));
 }
 
 fn add_red(main_window: &mut MainWindow, ui: &mut Ui, coordinates: (usize, usize), interactive: bool) {
-    add_button(main_window, ui, coordinates, interactive, "❌".to_owned(), Color32::RED);
+    add_button(main_window, ui, coordinates, interactive, "❌".to_owned(), theme_color(main_window.renderer.theme.x));
 }
 
 fn add_button(main_window: &mut MainWindow, ui: &mut Ui, coordinates: (usize, usize), interactive: bool, text: String, color: Color32){
-    if ui.add(Button::new(RichText::new(text).color(color).size(main_window.tile_size).background_color(get_tile_color(&main_window.game_state, &main_window.winner)))
-        .fill(get_tile_color(&main_window.game_state, &main_window.winner))
-        .min_size(Vec2 { x: main_window.tile_size, y: main_window.tile_size })
+    let stroke = if main_window.forced_cells.contains(&coordinates) {
+        Stroke::new(4.0, theme_color(main_window.renderer.theme.highlight))
+    } else {
+        Stroke::NONE
+    };
+    if ui.add(Button::new(RichText::new(text).color(color).size(main_window.renderer.tile_size).background_color(get_tile_color(&main_window.renderer.theme, &main_window.game_state, &main_window.winner)))
+        .fill(get_tile_color(&main_window.renderer.theme, &main_window.game_state, &main_window.winner))
+        .stroke(stroke)
+        .min_size(Vec2 { x: main_window.renderer.tile_size, y: main_window.renderer.tile_size })
         .rounding(Rounding::none()))
     .clicked() && interactive{
-        let _ = main_window.game_state.play(coordinates, main_window.chosen_tile);
-        evaluate_game_state(&main_window.game_state, &mut main_window.winner);
+        if main_window.screen == Puzzles {
+            main_window.answer_puzzle(coordinates);
+        } else if let Some(clicks) = main_window.clicks() {
+            let _ = clicks.send((coordinates, main_window.chosen_tile));
+        }
     }
 }
 
-fn get_tile_color(game_state: &GameState, winner: &Option<Player>) -> Color32 {
+fn get_tile_color(theme: &Theme, game_state: &GameState, winner: &Option<Player>) -> Color32 {
     match winner {
-        Some(winner) => get_player_color(*winner),
-        None => get_player_color(game_state.turn_player)
+        Some(winner) => get_player_color(theme, *winner),
+        None => get_player_color(theme, game_state.turn_player)
     }
 }
 
@@ -180,63 +878,73 @@ fn evaluate_game_state(game_state: &GameState, winner: &mut Option<Player>) {
     }
 }
 
-fn player_fmt(player: &Player) -> RichText {
-    match *player {
-        Order => RichText::new("Order").color(get_player_color(Order)).size(32.0),
-        Chaos => RichText::new("Chaos").color(get_player_color(Chaos)).size(32.0)
-    }
+fn player_fmt(renderer: &Renderer, player: &Player) -> RichText {
+    return RichText::new(renderer.language.player(player)).color(get_player_color(&renderer.theme, *player)).size(32.0);
 }
 
-fn get_player_color(player: Player) -> Color32 {
-    match player {
-        Order => Color32::from_rgb(204, 170, 0),
-        Chaos => Color32::from_rgb(51, 153, 0)
-    }
+/// A button label from the catalogue, padded so that the buttons are not cramped.
+fn button_text(language: Language, label: &str, size: f32) -> RichText {
+    return RichText::new(format!(" {} ", language.text(label))).size(size);
+}
+
+fn get_player_color(theme: &Theme, player: Player) -> Color32 {
+    return theme_color(theme.player(&player));
 }
 
-fn welcome_screen_layout() -> LayoutJob {
+fn theme_color(color: ThemeColor) -> Color32 {
+    let (red, green, blue) = color.rgb();
+    return Color32::from_rgb(red, green, blue);
+}
+
+fn welcome_screen_layout(renderer: &Renderer) -> LayoutJob {
     let mut job = LayoutJob::default();
     job.wrap.max_width = f32::INFINITY;
-    job.append("Welcome to the ", 0.0, default_text());
-    job.append("ORDER", 0.0, color_text(get_player_color(Order)));
-    job.append(" & ", 0.0, default_text());
-    job.append("CHAOS", 0.0, color_text(get_player_color(Chaos)));
-    job.append(" electronic simulator.\n\n\n", 0.0, default_text());
-    job.append("RULES:\n\n\n", 0.0, default_text());
-
-    job.append("• Order plays first, then turns alternate.\n\n", 0.0, default_text());
-
-    job.append("• Both players control both sets of pieces (", 0.0, default_text());
-    job.append("❌", 0.0, color_text(Color32::RED));
-    job.append(" and ", 0.0, default_text());
-    job.append("🌑", 0.0, color_text(Color32::BLUE));
-    job.append("). The game starts with the board empty.\n\n", 0.0, default_text());
-
-    job.append("• On each turn, a player places either an ", 0.0, default_text());
-    job.append("❌", 0.0, color_text(Color32::RED));
-    job.append(" or an ", 0.0, default_text());
-    job.append("🌑", 0.0, color_text(Color32::BLUE));
-    job.append(" on any open square. Once played, pieces cannot be moved\n\n", 0.0, default_text());
-
-    job.append("• ", 0.0, default_text());
-    job.append("ORDER", 0.0, color_text(get_player_color(Order)));
-    job.append(" aims to get exactly five like pieces in a row either vertically, horizontally, or diagonally.\n\n", 0.0, default_text());
-
-    job.append("• ", 0.0, default_text());
-    job.append("CHAOS", 0.0, color_text(get_player_color(Chaos)));
-    job.append(" aims to fill the board without completion of a line of five like pieces.\n\n", 0.0, default_text());
-
-    job.append("• Six-in-a-row does not qualify as a win", 0.0, default_text());
+    append_message(&mut job, renderer, WELCOME);
+    job.append("\n\n\n", 0.0, default_text());
+    job.append(&format!("{}\n\n\n", renderer.language.text("RULES:")), 0.0, default_text());
+    for (index, rule) in RULES.iter().enumerate() {
+        job.append("• ", 0.0, default_text());
+        append_message(&mut job, renderer, rule);
+        if index + 1 < RULES.len() {
+            job.append("\n\n", 0.0, default_text());
+        }
+    }
     job
 }
 
+/// Appends a message of the catalogue, with its pieces and players in their colours.
+fn append_message(job: &mut LayoutJob, renderer: &Renderer, message: &str) {
+    let theme = &renderer.theme;
+    for segment in renderer.language.segments(message) {
+        match segment {
+            Segment::Text(text) => job.append(text, 0.0, default_text()),
+            Segment::Piece(Red) => job.append("❌", 0.0, color_text(theme_color(theme.x))),
+            Segment::Piece(_) => job.append("🌑", 0.0, color_text(theme_color(theme.o))),
+            Segment::Player(player) => job.append(&renderer.language.player(&player).to_uppercase(), 0.0, color_text(get_player_color(theme, player))),
+            Segment::Title => {
+                append_message(job, renderer, "{Order}");
+                job.append(" & ", 0.0, default_text());
+                append_message(job, renderer, "{Chaos}");
+            }
+        }
+    }
+}
+
 fn end_screen_layout(state: &MainWindow) -> LayoutJob {
     let mut job = LayoutJob::default();
     job.wrap.max_width = f32::INFINITY;
+    let language = state.renderer.language;
     match state.winner {
-        Some(Order) => job.append("ORDER Won!", 0.0, color_text(get_player_color(Order))),
-        Some(Chaos) => job.append("CHAOS Won!", 0.0, color_text(get_player_color(Chaos))),
-        None => job.append("I don't know what happened, but it's a DRAW!", 0.0, color_text(Color32::from_rgb(255, 25, 217)))
+        Some(winner) => {
+            let name = language.player(&winner).to_uppercase();
+            job.append(&language.format("{} Won!", &[&name]), 0.0, color_text(get_player_color(&state.renderer.theme, winner)));
+        }
+        None => job.append(language.text("I don't know what happened, but it's a DRAW!"), 0.0, color_text(theme_color(state.renderer.theme.highlight)))
+    }
+    match state.record.tag("Termination") {
+        Some("Time forfeit") => job.append(&format!(" {}", language.text("(on time)")), 0.0, default_text()),
+        Some("Abandoned") => job.append(&format!(" {}", language.text("(abandoned)")), 0.0, default_text()),
+        _ => {}
     }
     job
 }
@@ -0,0 +1,145 @@
+#![allow(clippy::needless_return)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use serde::{Deserialize, Serialize};
+use crate::config::parse_player;
+use crate::notation::{coordinates_fmt, parse_coordinates};
+use crate::players::Player;
+use crate::record::{player_name, GameRecord};
+use crate::state::GameState;
+use crate::tile::Tile;
+
+/// The version written by `to_json`. It is raised whenever a field changes meaning or disappears; new fields leave it alone.
+pub const JSON_VERSION: u32 = 1;
+
+/// A game in the form other tools read. Tiles are `"X"`, `"O"` and `"."`, cells are written like `"A1"`,
+/// and the board lists row 1 first:
+///
+/// ```json
+/// {
+///   "format": "order-and-chaos",
+///   "version": 1,
+///   "metadata": { "Chaos": "Random", "Order": "Human", "Seed": "1234" },
+///   "board": [["X", ".", ".", ".", ".", "."], ...],
+///   "turn_player": "Chaos",
+///   "moves": [{ "player": "Order", "tile": "X", "cell": "A1" }],
+///   "result": null
+/// }
+/// ```
+#[derive(Serialize, Deserialize)]
+pub struct GameDocument {
+    pub format: String,
+    pub version: u32,
+    /// Every tag of the record apart from the result.
+    pub metadata: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub state: GameState,
+    pub moves: Vec<MoveEntry>,
+    pub result: Option<Player>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MoveEntry {
+    pub player: Player,
+    pub tile: Tile,
+    pub cell: String,
+}
+
+const FORMAT: &str = "order-and-chaos";
+
+impl GameDocument {
+    pub fn from_record(record: &GameRecord) -> Result<Self, &'static str> {
+        let mut moves = Vec::new();
+        for (ply, (coordinates, tile)) in record.moves.iter().enumerate() {
+            let player = record.state_after(ply)?.turn_player;
+            moves.push(MoveEntry { player, tile: *tile, cell: coordinates_fmt(coordinates) });
+        }
+        return Ok(Self {
+            format: FORMAT.to_string(),
+            version: JSON_VERSION,
+            metadata: record.tags.iter().filter(|(name, _)| name != "Result").cloned().collect(),
+            state: record.final_state()?,
+            moves,
+            result: record.tag("Result").and_then(|result| parse_player(result).ok()),
+        });
+    }
+
+    /// Replays the moves and checks that they lead to the stored board.
+    pub fn to_record(&self) -> Result<GameRecord, &'static str> {
+        if self.format != FORMAT {
+            return Err("This is not an Order and Chaos game");
+        }
+        if self.version > JSON_VERSION {
+            return Err("The game was written by a newer version");
+        }
+        let mut record = GameRecord::default();
+        for (name, value) in &self.metadata {
+            record.set_tag(name, value);
+        }
+        record.set_tag("Result", self.result.as_ref().map_or("*", player_name));
+        let mut game_state = record.initial_state()?;
+        for entry in &self.moves {
+            if entry.player != game_state.turn_player {
+                return Err("A move is listed for the wrong player");
+            }
+            let coordinates = parse_coordinates(&entry.cell)?;
+            game_state.play(coordinates, entry.tile)?;
+            record.moves.push((coordinates, entry.tile));
+        }
+        if game_state.board != self.state.board || game_state.turn_player != self.state.turn_player {
+            return Err("The board does not match the moves");
+        }
+        return Ok(record);
+    }
+}
+
+pub fn to_json(record: &GameRecord) -> Result<String, &'static str> {
+    let document = GameDocument::from_record(record)?;
+    return serde_json::to_string_pretty(&document).map_err(|_| "Could not write the game as JSON");
+}
+
+pub fn parse_json(text: &str) -> Result<GameRecord, &'static str> {
+    let document: GameDocument = serde_json::from_str(text).map_err(|_| "Not a valid JSON game")?;
+    return document.to_record();
+}
+
+pub fn export_json(record: &GameRecord, path: &str) -> Result<(), &'static str> {
+    return fs::write(path, to_json(record)?).map_err(|_| "Could not write the JSON file");
+}
+
+#[cfg(test)]
+mod json_test {
+    use crate::json::{parse_json, to_json};
+    use crate::players::Player::Order;
+    use crate::record::GameRecord;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_round_trip() {
+        let mut record = GameRecord::new("Human", "Random", 1234);
+        record.moves = vec![((0, 0), Red), ((5, 5), Blue), ((2, 3), Red)];
+        record.set_resignation(Order.opponent());
+        let text = to_json(&record).expect("");
+        assert!(text.contains("\"version\": 1"));
+        assert!(text.contains("\"turn_player\": \"Chaos\""));
+        assert!(text.contains("\"cell\": \"D3\""));
+
+        let loaded = parse_json(&text).expect("");
+        assert_eq!(loaded.moves, record.moves);
+        for (name, value) in &record.tags {
+            assert_eq!(loaded.tag(name), Some(value.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_rejects_inconsistent_documents() {
+        let mut record = GameRecord::new("A", "B", 1);
+        record.moves = vec![((0, 0), Red), ((5, 5), Blue)];
+        let text = to_json(&record).expect("");
+        assert_eq!(parse_json(&text.replacen("\"O\"", "\"X\"", 1)).err(), Some("The board does not match the moves"));
+        assert_eq!(parse_json(&text.replace("\"player\": \"Chaos\"", "\"player\": \"Order\"")).err(), Some("A move is listed for the wrong player"));
+        assert_eq!(parse_json(&text.replace("\"version\": 1", "\"version\": 2")).err(), Some("The game was written by a newer version"));
+        assert!(parse_json("{}").is_err());
+    }
+}
@@ -0,0 +1,463 @@
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::fmt::Display;
+use crate::notation::parse_move;
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::state::Move;
+use crate::tile::Tile;
+use crate::tile::Tile::{Blue, Empty, Red};
+
+/// The greeting above the rules, with `{Title}` standing for the name of the game.
+pub const WELCOME: &str = "Welcome to the {Title} electronic simulator.";
+
+/// The rules, shown by both front ends. `{X}`, `{O}`, `{Order}` and `{Chaos}` stand for the pieces and players.
+pub const RULES: [&str; 6] = [
+    "Both players control both sets of pieces ({X} and {O}). The game starts with the board empty.",
+    "{Order} plays first, then turns alternate.",
+    "On each turn, a player places either an {X} or an {O} on any open square. Once played, pieces cannot be moved.",
+    "{Order} aims to get exactly five like pieces in a row either vertically, horizontally, or diagonally.",
+    "{Chaos} aims to fill the board without completion of a line of five like pieces.",
+    "Six-in-a-row does not qualify as a win.",
+];
+
+/// Everything that differs between languages. The English text of a message is its key, as with gettext, so a
+/// message that is missing from a catalogue is shown in English.
+struct Catalogue {
+    order: &'static str,
+    chaos: &'static str,
+    /// The letters of the columns, from left to right.
+    columns: [char; 6],
+    messages: &'static [(&'static str, &'static str)],
+}
+
+impl Catalogue {
+    fn coordinates(&self, coordinates: &(usize, usize)) -> String {
+        return format!("{}{}", self.columns[coordinates.1], coordinates.0 + 1);
+    }
+
+    fn move_text(&self, played_move: &Move) -> String {
+        let piece = match played_move.1 {
+            Red => "X",
+            Blue => "O",
+            Empty => "."
+        };
+        return format!("{} {}", piece, self.coordinates(&played_move.0));
+    }
+
+    /// Parses a move such as `X A1` or `O 5F` by turning the column letter of the cell into its letter in the notation.
+    /// The piece is always `X` or `O`, whatever letters the columns have.
+    fn parse_move(&self, input: &str) -> Result<Move, &'static str> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let [piece, cell] = words[..] else {
+            return Err("None of the Regular Expressions matched the input");
+        };
+        let mut notation_cell = String::new();
+        for character in cell.chars() {
+            if character.is_ascii_digit() {
+                notation_cell.push(character);
+                continue;
+            }
+            match self.columns.iter().position(|column| column.to_lowercase().eq(character.to_lowercase())) {
+                Some(index) => notation_cell.push((b'A' + index as u8) as char),
+                None => return Err("None of the Regular Expressions matched the input")
+            }
+        }
+        return parse_move(&format!("{} {}", piece, notation_cell));
+    }
+}
+
+const ENGLISH: Catalogue = Catalogue {
+    order: "Order",
+    chaos: "Chaos",
+    columns: ['A', 'B', 'C', 'D', 'E', 'F'],
+    messages: &[],
+};
+
+const GERMAN: Catalogue = Catalogue {
+    order: "Ordnung",
+    chaos: "Chaos",
+    columns: ['A', 'B', 'C', 'D', 'E', 'F'],
+    messages: &[
+        ("Welcome to the {Title} electronic simulator.", "Willkommen beim elektronischen {Title}-Simulator."),
+        ("Both players control both sets of pieces ({X} and {O}). The game starts with the board empty.", "Beide Spieler setzen beide Arten von Steinen ({X} und {O}). Das Spiel beginnt mit einem leeren Brett."),
+        ("{Order} plays first, then turns alternate.", "{Order} beginnt, danach wird abwechselnd gezogen."),
+        ("On each turn, a player places either an {X} or an {O} on any open square. Once played, pieces cannot be moved.", "In jedem Zug setzt ein Spieler ein {X} oder ein {O} auf ein freies Feld. Gesetzte Steine werden nicht mehr bewegt."),
+        ("{Order} aims to get exactly five like pieces in a row either vertically, horizontally, or diagonally.", "{Order} versucht, genau fünf gleiche Steine senkrecht, waagerecht oder diagonal in eine Reihe zu bringen."),
+        ("{Chaos} aims to fill the board without completion of a line of five like pieces.", "{Chaos} versucht, das Brett zu füllen, ohne dass eine Reihe aus fünf gleichen Steinen entsteht."),
+        ("Six-in-a-row does not qualify as a win.", "Sechs in einer Reihe zählen nicht als Sieg."),
+        ("RULES:", "REGELN:"),
+        ("Press any key to continue.", "Drücke eine Taste, um fortzufahren."),
+        ("Press any key to exit the game.", "Drücke eine Taste, um das Spiel zu beenden."),
+        ("{} Won!", "{} hat gewonnen!"),
+        ("I don't know what happened, but it's a DRAW!", "Ich weiß nicht, was passiert ist, aber es ist ein UNENTSCHIEDEN!"),
+        ("(on time)", "(auf Zeit)"),
+        ("(abandoned)", "(abgebrochen)"),
+        ("{} ran out of time.", "{} hat die Zeit überschritten."),
+        ("{} resigned.", "{} hat aufgegeben."),
+        ("{} abandoned the game.", "{} hat die Partie abgebrochen."),
+        ("{} to move.", "{} ist am Zug."),
+        ("Turn Player: ", "Am Zug: "),
+        ("Nobody", "Niemand"),
+        ("Seed: {}", "Startwert: {}"),
+        ("Evaluation: {} ({} favoured)", "Bewertung: {} ({} im Vorteil)"),
+        ("Live windows: {} {} / {} {}", "Offene Fenster: {} {} / {} {}"),
+        ("Open fours: {}, open threes: {}, double threats: {}", "Offene Vierer: {}, offene Dreier: {}, Doppeldrohungen: {}"),
+        ("Overline cells: {}, {} blocking potential: {}", "Felder für Überlängen: {}, Blockierpotenzial von {}: {}"),
+        ("Hint:", "Tipp:"),
+        ("Warning:", "Achtung:"),
+        ("{} can win right now with {}", "{} kann sofort gewinnen mit {}"),
+        ("{} threatens to win with {}", "{} droht zu gewinnen mit {}"),
+        ("{} is forced to neutralise {}", "{} muss {} entschärfen"),
+        ("{} can stop every threat, e.g. with {}", "{} kann jede Drohung abwehren, z. B. mit {}"),
+        ("{} cannot stop every threat.", "{} kann nicht jede Drohung abwehren."),
+        ("{} can win right now at {}", "{} kann sofort gewinnen auf {}"),
+        ("{} must neutralise {}", "{} muss {} entschärfen"),
+        ("{} cannot stop every threat at {}", "{} kann nicht jede Drohung abwehren auf {}"),
+        ("Best moves: {}", "Beste Züge: {}"),
+        ("{} wins in {} with {}", "{} gewinnt in {} mit {}"),
+        ("{} wins in {} whatever {} plays", "{} gewinnt in {}, was auch immer {} spielt"),
+        ("{} cannot force a win within {} moves", "{} kann innerhalb von {} Zügen keinen Sieg erzwingen"),
+        ("{} holds out for {} moves with {}", "{} hält {} Züge lang stand mit {}"),
+        ("Please select which pawn should be placed in which location on the board.", "Wähle, welcher Stein auf welches Feld des Bretts gesetzt werden soll."),
+        ("Examples: \nX A1\nO 5F", "Beispiele: \nX A1\nO 5F"),
+        ("Type resign to give up, save <file> to write the board as a diagram or load <file> to continue from one.", "Tippe resign, um aufzugeben, save <Datei>, um das Brett als Diagramm zu speichern, oder load <Datei>, um mit einem Diagramm weiterzuspielen."),
+        ("Saved the board to {}", "Das Brett wurde in {} gespeichert"),
+        ("None of the Regular Expressions matched the input: {}", "Die Eingabe ist kein Zug: {}"),
+        ("That was not a legal move. Message: {}", "Das war kein gültiger Zug. Meldung: {}"),
+        ("{} failed to move. Message: {}", "{} konnte nicht ziehen. Meldung: {}"),
+        ("That cell is already taken.", "Dieses Feld ist schon besetzt."),
+        ("Move {} of {}", "Zug {} von {}"),
+        ("Last move: {}", "Letzter Zug: {}"),
+        ("n: next, b: back, s: start, e: end, p: play to the end, q: quit", "n: weiter, b: zurück, s: Anfang, e: Ende, p: bis zum Ende abspielen, q: beenden"),
+        ("Puzzle {} of {}: {}", "Aufgabe {} von {}: {}"),
+        ("Puzzle {} of {}", "Aufgabe {} von {}"),
+        ("{} to win in {}", "{} gewinnt in {}"),
+        ("{} to stop all threats", "{} wehrt alle Drohungen ab"),
+        ("Enter your move, or q to stop training.", "Gib deinen Zug ein oder q, um das Training zu beenden."),
+        ("Correct!", "Richtig!"),
+        ("Not quite. The answer was {}", "Nicht ganz. Die Lösung war {}"),
+        ("Success rate: {}% over {} attempts", "Erfolgsquote: {} % bei {} Versuchen"),
+        ("Success rate: {}%", "Erfolgsquote: {} %"),
+        ("Click the cell to play the selected pawn on.", "Klicke auf das Feld, auf das der gewählte Stein gesetzt werden soll."),
+        ("Press enter to start the next game, with sides swapped.", "Drücke Enter, um das nächste Spiel mit getauschten Seiten zu beginnen."),
+        ("Playing {} against {}", "Du spielst {} gegen {}"),
+        ("Play again with sides swapped? (y/n)", "Noch einmal mit getauschten Seiten spielen? (y/n)"),
+        ("Serving the HTTP API on port {}", "Die HTTP-Schnittstelle läuft auf Port {}"),
+        ("Waiting for a player to connect on port {}", "Warte auf Port {} auf einen Mitspieler"),
+        ("Could not join the game: {}", "Beitritt zum Spiel fehlgeschlagen: {}"),
+        ("Waiting for the other player...", "Warte auf den anderen Spieler ..."),
+        ("The other player left.", "Der andere Spieler hat das Spiel verlassen."),
+        ("Add a comment for your opponent, or press enter to skip:", "Schreibe einen Kommentar für deinen Gegner oder drücke Enter, um keinen zu schreiben:"),
+        ("Saved {}. Send it to {} for the next move.", "{} wurde gespeichert. Schicke die Datei für den nächsten Zug an {}."),
+        ("It is not your turn. Send the file to your opponent.", "Du bist nicht am Zug. Schicke die Datei an deinen Gegner."),
+        ("Analysing the game...", "Das Spiel wird analysiert ..."),
+        ("Turning point: move {}", "Wendepunkt: Zug {}"),
+        ("Turning point: move {}, {} {}", "Wendepunkt: Zug {}, {} {}"),
+        ("Turning point: none", "Wendepunkt: keiner"),
+        ("{}: {} blunders, {} missed wins", "{}: {} Patzer, {} verpasste Siege"),
+        ("{}: {} blunders, {} missed saves", "{}: {} Patzer, {} verpasste Rettungen"),
+        ("Analysis", "Analyse"),
+        ("Game", "Spiel"),
+        ("Winner", "Sieger"),
+        ("Score: {} {} - {} {}", "Stand: {} {} - {} {}"),
+        ("{} wins the match {} - {} after {} games", "{} gewinnt das Match {} - {} nach {} Spielen"),
+        ("The match is drawn {} - {} after {} games", "Das Match endet unentschieden {} - {} nach {} Spielen"),
+        ("{}, best was {}", "{}, am besten war {}"),
+        ("Missed win", "Verpasster Sieg"),
+        ("Missed save", "Verpasste Rettung"),
+        ("Blunder", "Patzer"),
+        ("Skipping {}. Message: {}", "{} wird übersprungen. Meldung: {}"),
+        ("Wrote {} book moves from {} saved and {} self-play games to {}", "{} Buchzüge aus {} gespeicherten und {} selbst gespielten Partien wurden in {} geschrieben"),
+        ("Wrote {} puzzles to {}", "{} Aufgaben wurden in {} geschrieben"),
+        ("Saved the diagram to {}", "Das Diagramm wurde in {} gespeichert"),
+        ("Exported to {}", "Nach {} exportiert"),
+        ("Export to {}", "Nach {} exportieren"),
+        ("{} vs {}", "{} gegen {}"),
+        ("Ignoring {}: {}", "{} wird ignoriert: {}"),
+        ("Unknown theme \"{}\". Expected one of {} or a theme from the configuration file.", "Unbekanntes Farbschema \"{}\". Erwartet wird eines von {} oder ein Farbschema aus der Konfigurationsdatei."),
+        ("Unknown language. Expected en or de", "Unbekannte Sprache. Erwartet wird en oder de"),
+        ("The game record {} does not contain a seed.", "Die Partie {} enthält keinen Startwert."),
+        ("Could not start \"{}\". Message: {}", "\"{}\" konnte nicht gestartet werden. Meldung: {}"),
+        ("Continue", "Weiter"),
+        ("Settings", "Einstellungen"),
+        ("Replay", "Wiederholung"),
+        ("Puzzles", "Aufgaben"),
+        ("Back", "Zurück"),
+        ("Play", "Abspielen"),
+        ("Pause", "Pause"),
+        ("Resign", "Aufgeben"),
+        ("Save diagram", "Diagramm speichern"),
+        ("Next Puzzle", "Nächste Aufgabe"),
+        ("Analyse", "Analysieren"),
+        ("Export", "Exportieren"),
+        ("Exit Game", "Spiel beenden"),
+        ("Next Game", "Nächstes Spiel"),
+        ("Rematch", "Revanche"),
+        ("Select Pawn:", "Stein wählen:"),
+        ("Computer Opponent:", "Computergegner:"),
+        ("Difficulty:", "Spielstärke:"),
+        ("beginner", "Anfänger"),
+        ("casual", "Gelegenheitsspieler"),
+        ("intermediate", "Fortgeschritten"),
+        ("advanced", "Stark"),
+        ("expert", "Experte"),
+        ("Current Active Player:", "Am Zug:"),
+        ("Clocks:", "Uhren:"),
+        ("Evaluation:", "Bewertung:"),
+        ("Evaluation", "Bewertung"),
+        ("Seed:", "Startwert:"),
+        ("Tile Size:", "Feldgröße:"),
+        ("Milliseconds per move:", "Millisekunden pro Zug:"),
+        ("Space Already Occupied", "Das Feld ist schon besetzt"),
+        ("None of the Regular Expressions matched the input", "Die Eingabe ist kein Zug"),
+        ("Moves are written as a pawn and a cell, e.g. X A1", "Ein Zug besteht aus einem Stein und einem Feld, z. B. X A1"),
+        ("Not a valid column", "Keine gültige Spalte"),
+        ("Not a valid row", "Keine gültige Zeile"),
+        ("Not a valid cell", "Kein gültiges Feld"),
+        ("Not a valid pawn", "Kein gültiger Stein"),
+        ("A board needs six rows", "Ein Brett braucht sechs Zeilen"),
+        ("Every row of a board needs six cells", "Jede Zeile eines Bretts braucht sechs Felder"),
+        ("Cells must be X, O or .", "Felder müssen X, O oder . sein"),
+        ("A diagram needs six rows", "Ein Diagramm braucht sechs Zeilen"),
+        ("Every row of a diagram needs six cells", "Jede Zeile eines Diagramms braucht sechs Felder"),
+        ("The rows of the diagram are out of order", "Die Zeilen des Diagramms stehen in der falschen Reihenfolge"),
+        ("The board is full", "Das Brett ist voll"),
+        ("The game is already over", "Das Spiel ist schon vorbei"),
+        ("It is not your turn", "Du bist nicht am Zug"),
+        ("The record contains an illegal move", "Die Partie enthält einen ungültigen Zug"),
+        ("This is not an Order and Chaos game", "Das ist keine Partie Order and Chaos"),
+        ("Positions cannot be loaded in a network game", "In einem Netzwerkspiel können keine Stellungen geladen werden"),
+        ("Positions cannot be loaded in a correspondence game", "In einer Fernpartie können keine Stellungen geladen werden"),
+        ("Positions cannot be loaded in the window", "Im Fenster können keine Stellungen geladen werden"),
+        ("The game file was changed or the secret is wrong: its checksum does not match", "Die Spieldatei wurde verändert oder das Geheimnis ist falsch: ihre Prüfsumme stimmt nicht"),
+        ("Could not read the diagram", "Das Diagramm konnte nicht gelesen werden"),
+        ("Could not write the diagram", "Das Diagramm konnte nicht geschrieben werden"),
+        ("Could not encode the diagram", "Das Diagramm konnte nicht kodiert werden"),
+        ("Could not read the game record", "Die Partie konnte nicht gelesen werden"),
+        ("Could not write the game record", "Die Partie konnte nicht geschrieben werden"),
+        ("Could not read the game file", "Die Spieldatei konnte nicht gelesen werden"),
+        ("Could not write the game file", "Die Spieldatei konnte nicht geschrieben werden"),
+        ("Could not write the game as JSON", "Das Spiel konnte nicht als JSON geschrieben werden"),
+        ("Could not write the JSON file", "Die JSON-Datei konnte nicht geschrieben werden"),
+        ("Could not write the analysis", "Die Analyse konnte nicht geschrieben werden"),
+        ("Could not read the puzzle file", "Die Aufgabendatei konnte nicht gelesen werden"),
+        ("Could not write the puzzle file", "Die Aufgabendatei konnte nicht geschrieben werden"),
+        ("Could not read the opening book", "Das Eröffnungsbuch konnte nicht gelesen werden"),
+        ("Could not write the opening book", "Das Eröffnungsbuch konnte nicht geschrieben werden"),
+        ("Could not read the match", "Das Match konnte nicht gelesen werden"),
+        ("Could not write the match", "Das Match konnte nicht geschrieben werden"),
+        ("The match file contains no games", "Die Match-Datei enthält keine Spiele"),
+        ("Could not read the configuration file", "Die Konfigurationsdatei konnte nicht gelesen werden"),
+        ("Could not write the configuration file", "Die Konfigurationsdatei konnte nicht geschrieben werden"),
+        ("Could not create the configuration directory", "Das Konfigurationsverzeichnis konnte nicht angelegt werden"),
+        ("Could not write the preferences", "Die Einstellungen konnten nicht geschrieben werden"),
+        ("The configuration file is not valid TOML", "Die Konfigurationsdatei ist kein gültiges TOML"),
+        ("There is no configuration directory", "Es gibt kein Konfigurationsverzeichnis"),
+        ("Could not connect", "Die Verbindung konnte nicht hergestellt werden"),
+        ("Could not listen on the port", "Der Port kann nicht geöffnet werden"),
+        ("Could not accept a connection", "Eine Verbindung konnte nicht angenommen werden"),
+        ("Could not set up the connection", "Die Verbindung konnte nicht eingerichtet werden"),
+        ("The connection broke", "Die Verbindung ist abgebrochen"),
+        ("The connection was closed", "Die Verbindung wurde geschlossen"),
+        ("The opponent left", "Der Gegner hat das Spiel verlassen"),
+        ("The games are out of sync", "Die Spiele laufen nicht mehr gleich"),
+        ("Could not start the engine", "Die Engine konnte nicht gestartet werden"),
+        ("The engine stopped answering", "Die Engine antwortet nicht mehr"),
+        ("The engine reported an error", "Die Engine hat einen Fehler gemeldet"),
+        ("The agent crashed", "Der Spieler ist abgestürzt"),
+        ("Error Reading from stdin", "Die Eingabe konnte nicht gelesen werden"),
+        ("The window was closed", "Das Fenster wurde geschlossen"),
+    ],
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+}
+
+/// A piece of a message that the front ends draw in their own way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Piece(Tile),
+    Player(Player),
+    Title,
+}
+
+impl Language {
+    /// Takes a code such as `de`, a locale such as `de_AT.UTF-8` or the name of the language.
+    pub fn parse(name: &str) -> Result<Self, &'static str> {
+        let code = name.trim().split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+        match code.as_str() {
+            "en" | "english" => return Ok(Language::English),
+            "de" | "german" | "deutsch" => return Ok(Language::German),
+            _ => return Err("Unknown language. Expected en or de")
+        }
+    }
+
+    /// The language of the environment this process runs in, or English.
+    pub fn detect() -> Self {
+        return Self::from_environment(|name| env::var(name).ok());
+    }
+
+    /// Follows gettext: `LANGUAGE` lists languages in order of preference, then the locale of `LC_ALL`, `LC_MESSAGES`
+    /// or `LANG` decides, whichever is set first.
+    pub fn from_environment(variable: impl Fn(&str) -> Option<String>) -> Self {
+        for name in ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"] {
+            if let Some(value) = variable(name).filter(|value| !value.is_empty()) {
+                return value.split(':').find_map(|code| Self::parse(code).ok()).unwrap_or(Language::English);
+            }
+        }
+        return Language::English;
+    }
+
+    fn catalogue(&self) -> &'static Catalogue {
+        match self {
+            Language::English => &ENGLISH,
+            Language::German => &GERMAN
+        }
+    }
+
+    /// The translation of a message given in English.
+    pub fn text<'a>(&self, message: &'a str) -> &'a str {
+        return self.catalogue().messages.iter()
+            .find(|(english, _)| *english == message)
+            .map_or(message, |(_, translation)| translation);
+    }
+
+    /// The translation of a message given in English, with each `{}` replaced by the next argument.
+    pub fn format(&self, message: &str, arguments: &[&dyn Display]) -> String {
+        let mut parts = self.text(message).split("{}");
+        let mut text = parts.next().unwrap_or_default().to_string();
+        for (index, part) in parts.enumerate() {
+            if let Some(argument) = arguments.get(index) {
+                text.push_str(&argument.to_string());
+            }
+            text.push_str(part);
+        }
+        return text;
+    }
+
+    pub fn player(&self, player: &Player) -> &'static str {
+        match player {
+            Order => self.catalogue().order,
+            Chaos => self.catalogue().chaos
+        }
+    }
+
+    pub fn columns(&self) -> [char; 6] {
+        return self.catalogue().columns;
+    }
+
+    /// A cell such as `A1`, where the letter is the column and the number the row.
+    pub fn coordinates(&self, coordinates: &(usize, usize)) -> String {
+        return self.catalogue().coordinates(coordinates);
+    }
+
+    pub fn move_text(&self, played_move: &Move) -> String {
+        return self.catalogue().move_text(played_move);
+    }
+
+    /// Parses a move typed with the column letters of this language. Files and the engine protocol always use `A` to `F`.
+    pub fn parse_move(&self, input: &str) -> Result<Move, &'static str> {
+        return self.catalogue().parse_move(input);
+    }
+
+    /// Splits a translated message into text and the `{X}`, `{O}`, `{Order}`, `{Chaos}` and `{Title}` it contains.
+    pub fn segments<'a>(&self, message: &'a str) -> Vec<Segment<'a>> {
+        let mut segments = Vec::new();
+        let mut rest = self.text(message);
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(length) => start + length,
+                None => break
+            };
+            let segment = match &rest[start + 1..end] {
+                "X" => Segment::Piece(Red),
+                "O" => Segment::Piece(Blue),
+                "Order" => Segment::Player(Order),
+                "Chaos" => Segment::Player(Chaos),
+                "Title" => Segment::Title,
+                _ => Segment::Text(&rest[start..=end])
+            };
+            if start > 0 {
+                segments.push(Segment::Text(&rest[..start]));
+            }
+            segments.push(segment);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest));
+        }
+        return segments;
+    }
+}
+
+#[cfg(test)]
+mod locale_test {
+    use std::collections::HashMap;
+    use crate::locale::{Catalogue, Language, Segment, GERMAN, RULES};
+    use crate::players::Player::Order;
+    use crate::tile::Tile::{Blue, Red};
+
+    fn language(variables: &[(&str, &str)]) -> Language {
+        let variables: HashMap<String, String> = variables.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        return Language::from_environment(|name| variables.get(name).cloned());
+    }
+
+    #[test]
+    fn test_selection() {
+        assert_eq!(Language::parse("de_AT.UTF-8"), Ok(Language::German));
+        assert_eq!(Language::parse("English"), Ok(Language::English));
+        assert!(Language::parse("fr").is_err());
+        assert_eq!(language(&[]), Language::English);
+        assert_eq!(language(&[("LANG", "de_DE.UTF-8")]), Language::German);
+        assert_eq!(language(&[("LC_ALL", "C"), ("LANG", "de_DE.UTF-8")]), Language::English);
+        assert_eq!(language(&[("LANGUAGE", "fr:de"), ("LANG", "en_GB.UTF-8")]), Language::German);
+    }
+
+    #[test]
+    fn test_messages() {
+        let german = Language::German;
+        assert_eq!(Language::English.format("{} Won!", &[&"Order"]), "Order Won!");
+        assert_eq!(german.format("{} Won!", &[&german.player(&Order)]), "Ordnung hat gewonnen!");
+        assert_eq!(german.text("A message nobody translated"), "A message nobody translated");
+        assert_eq!(german.move_text(&((0, 2), Red)), "X C1");
+        assert_eq!(german.parse_move("o b6"), Ok(((5, 1), Blue)));
+        assert_eq!(Language::English.segments(RULES[0])[..3], [Segment::Text("Both players control both sets of pieces ("), Segment::Piece(Red), Segment::Text(" and ")]);
+        assert_eq!(german.segments("{Title}!"), [Segment::Title, Segment::Text("!")]);
+    }
+
+    #[test]
+    fn test_columns_of_a_catalogue() {
+        let catalogue = Catalogue { order: "Order", chaos: "Chaos", columns: ['U', 'V', 'W', 'X', 'Y', 'Z'], messages: &[] };
+        assert_eq!(catalogue.move_text(&((0, 3), Red)), "X X1");
+        assert_eq!(catalogue.coordinates(&(5, 0)), "U6");
+        assert_eq!(catalogue.parse_move("x x1"), Ok(((0, 3), Red)));
+        assert_eq!(catalogue.parse_move("O 6u\n"), Ok(((5, 0), Blue)));
+        assert_eq!(catalogue.parse_move("O Z2"), Ok(((1, 5), Blue)));
+        assert!(catalogue.parse_move("X A1").is_err());
+        assert!(catalogue.parse_move("U U1").is_err());
+        assert!(catalogue.parse_move("zzX X1junk").is_err());
+        assert!(catalogue.parse_move("foo X X1 bar").is_err());
+    }
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut found: Vec<&str> = text.match_indices('{').map(|(start, _)| &text[start..start + text[start..].find('}').unwrap_or(0) + 1]).collect();
+        found.sort();
+        return found;
+    }
+
+    #[test]
+    fn test_translations_keep_their_placeholders() {
+        for (english, german) in GERMAN.messages {
+            assert_eq!(placeholders(english), placeholders(german), "{}", english);
+        }
+        for rule in RULES {
+            assert_ne!(Language::German.text(rule), rule);
+        }
+    }
+}
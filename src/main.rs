@@ -1,11 +1,32 @@
+#![allow(clippy::needless_return)]
+
 use crate::players::Player::{Chaos, Order};
-use crate::state::{GameState, make_a_random_move};
-use crate::tile::Tile::{Blue, Red};
+use crate::state::GameState;
+use crate::agent::{Agent, computer_agent, Pairing, TerminalHuman, Turn};
+use crate::players::Player;
+use crate::protocol::run_engine_protocol;
+use crate::tile::Tile::{Blue, Empty, Red};
 use rand::Rng;
+use chrono::Utc;
 use std::io;
-use regex::Regex;
-use crate::config::read_options;
-use crate::display::{clear_output, show_error_message, show_exit_dialog, show_game_state, show_input_prompt, show_welcome_text, show_winner};
+use std::thread;
+use std::time::Duration;
+use crate::analysis::{analyse_game, best_moves};
+use crate::cli::Command;
+use crate::config::{read_options, Options};
+use crate::display::{clear_output, Renderer, show_abandonment, show_analysis, show_best_moves, show_clocks, show_comment_prompt, show_correspondence, show_correspondence_saved, show_error_message, show_exit_dialog, show_game_state, show_network_game, show_rematch_prompt, show_resignation, show_match, show_next_game_prompt, show_puzzle, show_puzzle_result, show_replay, show_seed, show_solution, show_success_rate, show_time_forfeit, show_welcome_text, show_winner};
+use crate::record::GameRecord;
+use crate::book::{add_self_play, Book};
+use crate::clock::Clocks;
+use crate::matches::{home_side, Match};
+use crate::network::NetworkSession;
+use crate::server::run_http_server;
+use crate::correspondence::Correspondence;
+use crate::json::export_json;
+use crate::diagram::{Arrow, Diagram};
+use crate::replay::Replay;
+use crate::notation::parse_board;
+use crate::puzzle::{generate_puzzles, load_puzzles, save_puzzles, solve};
 use crate::gui::show_main_screen;
 use crate::tile::Tile;
 
@@ -15,105 +36,441 @@ mod players;
 mod config;
 mod display;
 mod gui;
+mod evaluation;
+mod engine;
+mod notation;
+mod protocol;
+mod agent;
+mod record;
+mod book;
+mod puzzle;
+mod analysis;
+mod replay;
+mod clock;
+mod matches;
+mod network;
+mod server;
+mod correspondence;
+mod json;
+mod diagram;
+mod preferences;
+mod cli;
+mod locale;
+mod terminal;
+mod theme;
 
+/// How many moves are listed when a board is analysed.
+const BEST_MOVE_COUNT: usize = 5;
 
 fn main() {
-    read_options();
-    if config::get().disable_gui {
-        welcome_screen();
-        let mut game_state = GameState::default_new();
-        show_game_state(&game_state);
-        game_loop(&mut game_state, config::get().ai_vs_ai_demo);
-        end_of_game_screen();
-    } else {
-        show_main_screen();
+    let (command, options) = read_options();
+    let renderer = Renderer::from_options(&options);
+    match &command {
+        Command::Book { file, games, from, .. } => build_opening_book(&options, &renderer, file, *games, from),
+        Command::GeneratePuzzles { file, count, .. } => write_puzzles(&options, &renderer, file, *count),
+        Command::Diagram { input, output, arrows, no_labels, cell_size } => draw_diagram(&renderer, input, output, arrows, *no_labels, *cell_size),
+        Command::Analyse { input, .. } => analyse(&options, &renderer, input),
+        Command::Solve { input, depth, .. } => solve_position(&renderer, input, *depth),
+        Command::Correspondence { file, side, secret, .. } => play_correspondence(&options, &renderer, file, *side, secret),
+        Command::Engine { .. } => run_engine_protocol(options.difficulty, options.seed()),
+        Command::Http { port, bind } => {
+            println!("{}", options.language.format("Serving the HTTP API on port {}", &[port]));
+            if let Err(v) = run_http_server(bind, *port) {
+                show_error_message(&renderer, v);
+            }
+        }
+        Command::Serve { port, bind, side, .. } => {
+            println!("{}", options.language.format("Waiting for a player to connect on port {}", &[port]));
+            match NetworkSession::serve(bind, *port, *side) {
+                Ok(session) => play_network(&options, &renderer, session),
+                Err(v) => show_error_message(&renderer, v)
+            }
+        }
+        _ if !options.disable_gui => show_main_screen(options),
+        Command::Replay { file, .. } => replay_viewer(&options, &renderer, file),
+        Command::Puzzles { file, .. } => puzzle_trainer(&renderer, file),
+        Command::Connect { address, .. } => {
+            match NetworkSession::connect(address) {
+                Ok(session) => play_network(&options, &renderer, session),
+                Err(v) => show_error_message(&renderer, v)
+            }
+        }
+        Command::Tournament { pairs, .. } => {
+            welcome_screen(&renderer);
+            play_match(&options, &renderer, *pairs);
+            end_of_game_screen(&renderer);
+        }
+        Command::Play { .. } | Command::Demo { .. } => {
+            welcome_screen(&renderer);
+            let mut pairing = Pairing::new(terminal_agent(&options, Order), terminal_agent(&options, Chaos));
+            let mut record = GameRecord::new(&pairing.order.name(), &pairing.chaos.name(), options.seed());
+            play_game(&options, &renderer, &mut pairing, &mut record);
+            save_record(&options, &renderer, &record);
+            if options.analyse {
+                review_game(&options, &renderer, &record);
+            }
+            end_of_game_screen(&renderer);
+        }
     }
 }
 
-fn welcome_screen() {
-    show_welcome_text();
+fn play_game(options: &Options, renderer: &Renderer, pairing: &mut Pairing, record: &mut GameRecord) {
+    let mut game_state = GameState::default_new();
+    let mut clocks = options.time_control.map(Clocks::new);
+    if let Some(clocks) = &clocks {
+        record.set_tag("TimeControl", &clocks.control.to_string());
+    }
+    show_seed(renderer, record.seed().unwrap_or_default());
+    show_game_state(renderer, &game_state);
+    if let Some(clocks) = &clocks {
+        show_clocks(renderer, clocks);
+    }
+    game_loop(renderer, &mut game_state, pairing, record, &mut clocks);
+}
+
+/// Plays game pairs between the participants set up for Order and Chaos, who swap sides after every game.
+fn play_match(options: &Options, renderer: &Renderer, pairs: usize) {
+    let mut participants = [terminal_agent(options, home_side(0)), terminal_agent(options, home_side(1))];
+    let mut series = Match::resume_or_new(options.match_file.as_ref(), &participants[0].name(), &participants[1].name(), pairs);
+    if !series.records.is_empty() {
+        show_match(renderer, &series);
+        let game_options = series.game_options(options);
+        participants = [terminal_agent(&game_options, home_side(0)), terminal_agent(&game_options, home_side(1))];
+    }
+    loop {
+        let game = series.records.len();
+        let [first, second] = participants;
+        let order_first = series.participant(game, Order) == 0;
+        let mut pairing = if order_first { Pairing::new(first, second) } else { Pairing::new(second, first) };
+        let mut record = series.next_record(series.game_options(options).seed());
+        play_game(options, renderer, &mut pairing, &mut record);
+        save_record(options, renderer, &record);
+        series.add_game(record);
+        show_match(renderer, &series);
+        if let Some(path) = &options.match_file {
+            if let Err(v) = series.save(path) {
+                show_error_message(renderer, v);
+            }
+        }
+        if series.is_over() {
+            break;
+        }
+        show_next_game_prompt(renderer);
+        read_console().unwrap_or_default();
+        clear_output();
+        let game_options = series.game_options(options);
+        participants = [terminal_agent(&game_options, home_side(0)), terminal_agent(&game_options, home_side(1))];
+    }
+}
+
+fn build_opening_book(options: &Options, renderer: &Renderer, path: &str, games: usize, from: &[String]) {
+    let mut book = Book::default();
+    let mut saved_games = 0;
+    for record_path in from {
+        match GameRecord::load(record_path).and_then(|record| book.add_game(&record, options.book_plies)) {
+            Ok(_) => saved_games += 1,
+            Err(v) => show_error_message(renderer, &options.language.format("Skipping {}. Message: {}", &[record_path, &options.language.text(v)]))
+        }
+    }
+    add_self_play(&mut book, games, options.book_plies, options.difficulty, options.seed());
+    match book.save(path) {
+        Ok(_) => println!("{}", options.language.format("Wrote {} book moves from {} saved and {} self-play games to {}", &[&book.len(), &saved_games, &games, &path])),
+        Err(v) => show_error_message(renderer, v)
+    }
+}
+
+fn write_puzzles(options: &Options, renderer: &Renderer, path: &str, count: usize) {
+    let puzzles = generate_puzzles(count, count * 10, options.seed());
+    match save_puzzles(&puzzles, path) {
+        Ok(_) => println!("{}", options.language.format("Wrote {} puzzles to {}", &[&puzzles.len(), &path])),
+        Err(v) => show_error_message(renderer, v)
+    }
+}
+
+fn puzzle_trainer(renderer: &Renderer, path: &str) {
+    let mut puzzles = match load_puzzles(path) {
+        Ok(v) => v,
+        Err(v) => return show_error_message(renderer, v)
+    };
+    let total = puzzles.len();
+    'puzzles: for index in 0..total {
+        clear_output();
+        show_puzzle(renderer, &puzzles[index], index + 1, total);
+        let answer = loop {
+            let user_input = read_console().unwrap_or_default();
+            if user_input.is_empty() || user_input.trim().eq_ignore_ascii_case("q") {
+                break 'puzzles;
+            }
+            match renderer.language.parse_move(&user_input) {
+                Ok(v) if Empty.eq(&puzzles[index].position.tile_at(v.0)) => break v,
+                Ok(_) => show_error_message(renderer, "That cell is already taken."),
+                Err(v) => show_error_message(renderer, v)
+            }
+        };
+        let correct = puzzles[index].answer(answer);
+        show_puzzle_result(renderer, &puzzles[index], correct);
+        if let Err(v) = save_puzzles(&puzzles, path) {
+            show_error_message(renderer, v);
+        }
+        show_success_rate(renderer, &puzzles);
+        read_console().unwrap_or_default();
+    }
+    show_success_rate(renderer, &puzzles);
+}
+
+fn replay_viewer(options: &Options, renderer: &Renderer, path: &str) {
+    let mut replay = match Replay::load(path) {
+        Ok(v) => v,
+        Err(v) => return show_error_message(renderer, v)
+    };
+    let speed = Duration::from_millis(options.replay_speed);
+    loop {
+        clear_output();
+        show_replay(renderer, &replay);
+        let user_input = read_console().unwrap_or_default();
+        if user_input.is_empty() {
+            return;
+        }
+        match user_input.trim().to_lowercase().as_str() {
+            "n" | "" => replay.forward(),
+            "b" => replay.back(),
+            "s" => replay.jump_to_start(),
+            "e" => replay.jump_to_end(),
+            "p" => {
+                while !replay.is_at_end() {
+                    thread::sleep(speed);
+                    replay.forward();
+                    clear_output();
+                    show_replay(renderer, &replay);
+                }
+            }
+            "q" => return,
+            _ => continue
+        }
+    }
+}
+
+fn welcome_screen(renderer: &Renderer) {
+    show_welcome_text(renderer);
     read_console().unwrap();
     clear_output();
 }
 
-fn end_of_game_screen() {
-    show_exit_dialog();
+fn end_of_game_screen(renderer: &Renderer) {
+    show_exit_dialog(renderer);
     read_console().unwrap();
 }
 
-fn game_loop(game_state: &mut GameState, ai_vs_ai: bool) {
+fn game_loop(renderer: &Renderer, game_state: &mut GameState, pairing: &mut Pairing, record: &mut GameRecord, clocks: &mut Option<Clocks>) {
+    let mut resigned = None;
+    let mut forfeited = None;
+    let mut abandoned = None;
     while game_state.can_order_win() && !game_state.is_in_order() {
-        if ai_vs_ai {
-            make_a_random_move(game_state);
-        } else {
-            ask_for_a_move(game_state);
+        let turn_player = game_state.turn_player;
+        let time_limit = clocks.as_ref().map(|clocks| clocks.move_budget(turn_player));
+        if let Some(clocks) = clocks.as_mut() {
+            clocks.start(turn_player);
+        }
+        let turn = pairing.agent(turn_player).select_move(game_state, time_limit);
+        if let Some(Err(loser)) = clocks.as_mut().map(|clocks| clocks.stop()) {
+            forfeited = Some(loser);
+            break;
+        }
+        let result = match turn {
+            Ok(Turn::Move((coordinates, tile))) => game_state.play(coordinates, tile).map(|_| Turn::Move((coordinates, tile))),
+            other => other
+        };
+        match result {
+            Ok(Turn::Move(played_move)) => record.moves.push(played_move),
+            Ok(Turn::Resign) => {
+                resigned = Some(turn_player);
+                break;
+            }
+            Ok(Turn::LoadPosition(position)) => {
+                match pairing.agent(turn_player.opponent()).position_loaded(&position) {
+                    Ok(_) => {
+                        *game_state = position;
+                        record.set_position(&position);
+                    }
+                    Err(v) => show_error_message(renderer, v)
+                }
+            }
+            Err(v) => {
+                show_error_message(renderer, &renderer.language.format("{} failed to move. Message: {}", &[&pairing.agent(turn_player).name(), &renderer.language.text(v)]));
+                abandoned = Some(turn_player);
+                break;
+            }
         }
         clear_output();
-        show_game_state(game_state);
+        show_game_state(renderer, game_state);
+        if let Some(clocks) = clocks {
+            show_clocks(renderer, clocks);
+        }
     }
-    if game_state.is_in_order() {
-        show_winner(Order);
+    pairing.order.game_over(game_state, resigned);
+    pairing.chaos.game_over(game_state, resigned);
+    if let Some(loser) = forfeited {
+        record.set_time_forfeit(loser);
+        show_time_forfeit(renderer, loser);
+    } else if let Some(loser) = abandoned {
+        record.set_abandonment(loser);
+        show_abandonment(renderer, loser);
+    } else if let Some(loser) = resigned {
+        record.set_resignation(loser);
+        show_resignation(renderer, loser);
+    } else if game_state.is_in_order() {
+        record.set_winner(Order);
+        show_winner(renderer, Order);
     } else {
-        show_winner(Chaos);
+        record.set_winner(Chaos);
+        show_winner(renderer, Chaos);
     }
 }
 
-fn random_pawn() -> Tile {
-    if rand::random() {
-        return Blue;
+/// Plays games against another copy of the program until one side declines a rematch.
+fn play_network(options: &Options, renderer: &Renderer, mut session: NetworkSession) {
+    loop {
+        let local_agent = terminal_agent(options, session.local);
+        let remote_agent: Box<dyn Agent> = Box::new(session.remote_agent());
+        let mut pairing = match session.local {
+            Order => Pairing::new(local_agent, remote_agent),
+            Chaos => Pairing::new(remote_agent, local_agent)
+        };
+        let mut record = GameRecord::new(&pairing.order.name(), &pairing.chaos.name(), options.seed());
+        let mut game_state = session.start;
+        show_network_game(renderer, &session);
+        show_game_state(renderer, &game_state);
+        game_loop(renderer, &mut game_state, &mut pairing, &mut record, &mut None);
+        save_record(options, renderer, &record);
+        show_rematch_prompt(renderer);
+        let wanted = read_console().unwrap_or_default().trim().eq_ignore_ascii_case("y");
+        match session.rematch(wanted) {
+            Ok(true) => session.swap_sides(),
+            Ok(false) => return,
+            Err(v) => return show_error_message(renderer, v)
+        }
+        clear_output();
     }
-    return Red;
 }
 
-fn random_coordinates() -> (usize, usize) {
-    let mut rng = rand::thread_rng();
-    return (rng.gen_range(0..6), rng.gen_range(0..6));
+fn draw_diagram(renderer: &Renderer, input: &str, path: &str, arrows: &[Arrow], no_labels: bool, cell_size: u32) {
+    let mut diagram = match Diagram::from_position_or_file(input) {
+        Ok(v) => v,
+        Err(v) => return show_error_message(renderer, v)
+    };
+    diagram.coordinates = !no_labels;
+    diagram.arrows = arrows.to_vec();
+    diagram.cell_size = cell_size;
+    match diagram.save(path) {
+        Ok(_) => println!("{}", renderer.language.format("Saved the diagram to {}", &[&path])),
+        Err(v) => show_error_message(renderer, v)
+    }
 }
 
-fn ask_for_a_move(game_state: &mut GameState) {
-    let mut played: bool = false;
-    while !played {
-        show_input_prompt();
-        let input;
-        let result_in = read_input();
-        match result_in {
-            Err(v) => {
-                show_error_message(v);
-                continue;
-            }
-            Ok(v) => input = v
+fn save_record(options: &Options, renderer: &Renderer, record: &GameRecord) {
+    if let Some(path) = &options.save {
+        if let Err(v) = record.save(path) {
+            show_error_message(renderer, v);
         }
+    }
+    if let Some(path) = &options.export_json {
+        if let Err(v) = export_json(record, path) {
+            show_error_message(renderer, v);
+        }
+    }
+}
 
-        let result = game_state.play(input.1, input.0);
-        match result {
-            Err(v) => show_error_message(&format!("That was not a legal move. Message: {}", v)),
-            _ => played = true
+fn review_game(options: &Options, renderer: &Renderer, record: &GameRecord) {
+    println!("{}", renderer.language.text("Analysing the game..."));
+    let analysis = match analyse_game(record, Duration::from_millis(options.analysis_time)) {
+        Ok(v) => v,
+        Err(v) => return show_error_message(renderer, v)
+    };
+    show_analysis(renderer, &analysis, record);
+    if let Some(path) = &options.analysis_file {
+        if let Err(v) = analysis.save(record, path) {
+            show_error_message(renderer, v);
         }
     }
 }
 
-fn read_input() -> Result<(Tile, (usize, usize)), &'static str> {
-    let regular_expression_1 = Regex::new(r"([X,O]) ([A-F])([1-6])").unwrap();
-    let regular_expression_2 = Regex::new(r"([X,O]) ([1-6])([A-F])").unwrap();
+/// Reviews a saved game, or shows the evaluation of a board and the moves the engine likes best.
+fn analyse(options: &Options, renderer: &Renderer, input: &str) {
+    if let Ok(game_state) = parse_board(input) {
+        show_game_state(renderer, &game_state);
+        show_best_moves(renderer, &best_moves(&game_state, Duration::from_millis(options.analysis_time), BEST_MOVE_COUNT));
+        return;
+    }
+    match GameRecord::load(input) {
+        Ok(record) => review_game(options, renderer, &record),
+        Err(v) => show_error_message(renderer, v)
+    }
+}
 
-    let user_input;
-    let io_result = read_console();
-    match io_result {
-        Err(v) => return Err(v),
-        Ok(v) => user_input = v
+fn solve_position(renderer: &Renderer, input: &str, depth: usize) {
+    let game_state = match parse_board(input).or_else(|_| GameRecord::load(input).and_then(|record| record.final_state())) {
+        Ok(v) => v,
+        Err(v) => return show_error_message(renderer, v)
+    };
+    match solve(&game_state, depth) {
+        Ok(solution) => show_solution(renderer, &game_state, &solution, depth),
+        Err(v) => show_error_message(renderer, v)
     }
+}
 
-    if regular_expression_1.is_match(&user_input) {
-        return parse_input(&user_input, true);
-    } else if regular_expression_2.is_match(&user_input) {
-        return parse_input(&user_input, false);
+/// Shows a correspondence game and, if it is the player's turn, adds their move and writes the file back.
+fn play_correspondence(options: &Options, renderer: &Renderer, path: &str, side: Player, secret: &str) {
+    let mut game = match Correspondence::load_or_new(path, secret) {
+        Ok(v) => v,
+        Err(v) => {
+            show_error_message(renderer, v);
+            return;
+        }
+    };
+    show_correspondence(renderer, &game);
+    let game_state = game.game_state();
+    if game.is_over() {
+        return;
     }
+    if side != game_state.turn_player {
+        show_error_message(renderer, "It is not your turn. Send the file to your opponent.");
+        return;
+    }
+    let played = match terminal_agent(options, side).select_move(&game_state, None) {
+        Ok(Turn::Move(played_move)) => {
+            show_comment_prompt(renderer);
+            let comment = read_console().unwrap_or_default();
+            game.play(side, played_move, &comment, Utc::now())
+        }
+        Ok(Turn::Resign) => game.resign(side),
+        Ok(Turn::LoadPosition(_)) => Err("Positions cannot be loaded in a correspondence game"),
+        Err(v) => Err(v)
+    };
+    match played.and_then(|_| game.save(path)) {
+        Ok(_) => {
+            show_correspondence(renderer, &game);
+            show_correspondence_saved(renderer, path, side.opponent());
+        }
+        Err(v) => show_error_message(renderer, v)
+    }
+}
+
+fn terminal_agent(options: &Options, player: Player) -> Box<dyn Agent> {
+    return computer_agent(options, player).unwrap_or_else(|| Box::new(TerminalHuman::new(Renderer::from_options(options))));
+}
+
+pub fn random_pawn<R: Rng>(rng: &mut R) -> Tile {
+    if rng.gen() {
+        return Blue;
+    }
+    return Red;
+}
 
-    show_error_message(&format!("None of the Regular Expressions matched the input: {}", user_input));
-    return Err("None of the Regular Expressions matched the input");
+pub fn random_coordinates<R: Rng>(rng: &mut R) -> (usize, usize) {
+    return (rng.gen_range(0..6), rng.gen_range(0..6));
 }
 
-fn read_console() -> Result<String, &'static str> {
+pub fn read_console() -> Result<String, &'static str> {
     let mut user_input = String::new();
     let stdin = io::stdin();
     let io_result = stdin.read_line(&mut user_input);
@@ -122,51 +479,3 @@ fn read_console() -> Result<String, &'static str> {
     }
     return Ok(user_input);
 }
-
-fn parse_input(input: &str, letter_first: bool) -> Result<(Tile, (usize, usize)), &'static str> {
-    let tile;
-    let split: Vec<&str> = input.split("").collect();
-    if split[1].eq_ignore_ascii_case("X") {
-        tile = Red;
-    } else {
-        tile = Blue;
-    }
-    let column: usize;
-    let row: usize;
-    let letter: &str;
-    let number: &str;
-    if letter_first {
-        letter = split[3];
-        number = split[4];
-    } else {
-        letter = split[4];
-        number = split[3];
-    }
-
-    row = number.parse().unwrap();
-    match letter {
-        "A" => {
-            column = 0;
-        }
-        "B" => {
-            column = 1;
-        }
-        "C" => {
-            column = 2;
-        }
-        "D" => {
-            column = 3;
-        }
-        "E" => {
-            column = 4;
-        }
-        "F" => {
-            column = 5;
-        }
-        _ => {
-            return Err("Not a valid column");
-        }
-    }
-
-    return Ok((tile, (row - 1, column)));
-}
\ No newline at end of file
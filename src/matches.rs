@@ -0,0 +1,199 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use crate::config::Options;
+use crate::locale::Language;
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::record::GameRecord;
+
+/// The side whose settings, such as `--computer` or `--order-engine`, describe each participant.
+pub fn home_side(participant: usize) -> Player {
+    if participant == 0 {
+        return Order;
+    }
+    return Chaos;
+}
+
+/// A series of game pairs between two participants who swap Order and Chaos after every game.
+///
+/// A match is saved as its game records one after the other, each tagged with the match, the game number and the
+/// number of games.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Match {
+    pub names: [String; 2],
+    pub games: usize,
+    pub records: Vec<GameRecord>,
+}
+
+impl Match {
+    pub fn new(first: &str, second: &str, pairs: usize) -> Self {
+        let (first, second) = if first == second {
+            (format!("{} 1", first), format!("{} 2", second))
+        } else {
+            (first.to_string(), second.to_string())
+        };
+        return Self { names: [first, second], games: pairs * 2, records: Vec::new() };
+    }
+
+    /// Picks up the unfinished match saved at `path`, or starts a new one.
+    pub fn resume_or_new(path: Option<&String>, first: &str, second: &str, pairs: usize) -> Self {
+        if let Some(Ok(series)) = path.map(|path| Self::load(path)) {
+            if !series.is_over() {
+                return series;
+            }
+        }
+        return Self::new(first, second, pairs);
+    }
+
+    /// The participant playing `player` in the game with the given index.
+    pub fn participant(&self, game: usize, player: Player) -> usize {
+        let order = game % 2;
+        match player {
+            Order => return order,
+            Chaos => return 1 - order
+        }
+    }
+
+    /// A record for the next game, tagged with the match and the participants' names.
+    pub fn next_record(&self, seed: u64) -> GameRecord {
+        let game = self.records.len();
+        let mut record = GameRecord::new(&self.names[self.participant(game, Order)], &self.names[self.participant(game, Chaos)], seed);
+        record.set_tag("Match", &format!("{} vs {}", self.names[0], self.names[1]));
+        record.set_tag("Game", &(game + 1).to_string());
+        record.set_tag("Games", &self.games.to_string());
+        return record;
+    }
+
+    /// The options for the next game, with the seed moved on so that computer players do not repeat themselves.
+    pub fn game_options(&self, options: &Options) -> Options {
+        let mut game_options = options.clone();
+        game_options.seed = Some(options.seed().wrapping_add(2 * self.records.len() as u64));
+        return game_options;
+    }
+
+    pub fn add_game(&mut self, record: GameRecord) {
+        self.records.push(record);
+    }
+
+    fn game_winner(&self, game: usize) -> Option<usize> {
+        match self.records[game].tag("Result") {
+            Some("Order") => return Some(self.participant(game, Order)),
+            Some("Chaos") => return Some(self.participant(game, Chaos)),
+            _ => return None
+        }
+    }
+
+    pub fn scores(&self) -> [usize; 2] {
+        let mut scores = [0, 0];
+        for game in 0..self.records.len() {
+            if let Some(winner) = self.game_winner(game) {
+                scores[winner] += 1;
+            }
+        }
+        return scores;
+    }
+
+    /// Whether all games are played or one participant can no longer be caught.
+    pub fn is_over(&self) -> bool {
+        let remaining = self.games.saturating_sub(self.records.len());
+        let [first, second] = self.scores();
+        return remaining == 0 || first.abs_diff(second) > remaining;
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        let [first, second] = self.scores();
+        if first > second {
+            return Some(0);
+        }
+        if second > first {
+            return Some(1);
+        }
+        return None;
+    }
+
+    pub fn score_table(&self, language: Language) -> String {
+        let (order, chaos) = (language.player(&Order), language.player(&Chaos));
+        let width = self.names.iter().map(String::as_str).chain([order, chaos]).map(|name| name.chars().count()).max().unwrap_or(0).max(6);
+        let mut table = format!("{:<5} {:<width$} {:<width$} {}\n", language.text("Game"), order, chaos, language.text("Winner"), width = width);
+        for game in 0..self.records.len() {
+            let winner = self.game_winner(game).map(|winner| self.names[winner].as_str()).unwrap_or("-");
+            table.push_str(&format!("{:<5} {:<width$} {:<width$} {}\n", game + 1, self.names[self.participant(game, Order)], self.names[self.participant(game, Chaos)], winner, width = width));
+        }
+        let [first, second] = self.scores();
+        table.push_str(&language.format("Score: {} {} - {} {}", &[&self.names[0], &first, &second, &self.names[1]]));
+        table.push('\n');
+        return table;
+    }
+
+    pub fn summary(&self, language: Language) -> String {
+        let [first, second] = self.scores();
+        match self.winner() {
+            Some(winner) => return language.format("{} wins the match {} - {} after {} games", &[&self.names[winner], &first.max(second), &first.min(second), &self.records.len()]),
+            None => return language.format("The match is drawn {} - {} after {} games", &[&first, &second, &self.records.len()])
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let records: Vec<String> = self.records.iter().map(|record| record.to_text()).collect();
+        return records.join("\n");
+    }
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let records = GameRecord::parse_all(text)?;
+        let first = records.first().ok_or("The match file contains no games")?;
+        let games = first.tag("Games").and_then(|games| games.parse().ok()).ok_or("The match file does not say how many games are played")?;
+        let names = [first.tag("Order").unwrap_or("?").to_string(), first.tag("Chaos").unwrap_or("?").to_string()];
+        return Ok(Self { names, games, records });
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), &'static str> {
+        return fs::write(path, self.to_text()).map_err(|_| "Could not write the match");
+    }
+
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        let text = fs::read_to_string(path).map_err(|_| "Could not read the match")?;
+        return Self::parse(&text);
+    }
+}
+
+#[cfg(test)]
+mod matches_test {
+    use crate::locale::Language;
+    use crate::matches::Match;
+    use crate::players::Player::{Chaos, Order};
+
+    #[test]
+    fn test_sides_swap_and_scores_add_up() {
+        let mut series = Match::new("Human", "Engine", 2);
+        assert_eq!(series.participant(0, Order), 0);
+        assert_eq!(series.participant(1, Order), 1);
+        for winner in [Order, Order, Chaos] {
+            let mut record = series.next_record(1);
+            record.set_winner(winner);
+            series.add_game(record);
+        }
+        assert_eq!(series.records[1].tag("Order"), Some("Engine"));
+        assert_eq!(series.scores(), [1, 2]);
+        assert!(!series.is_over());
+        assert!(series.score_table(Language::English).contains("Score: Human 1 - 2 Engine"));
+        assert!(series.score_table(Language::German).starts_with("Spiel Ordnung Chaos   Sieger\n"));
+
+        let parsed = Match::parse(&series.to_text()).expect("");
+        assert_eq!(parsed, series);
+    }
+
+    #[test]
+    fn test_match_ends_when_decided() {
+        let mut series = Match::new("Engine", "Engine", 2);
+        assert_eq!(series.names, ["Engine 1".to_string(), "Engine 2".to_string()]);
+        for winner in [Order, Chaos, Order] {
+            let mut record = series.next_record(1);
+            record.set_winner(winner);
+            series.add_game(record);
+        }
+        assert!(series.is_over());
+        assert_eq!(series.summary(Language::English), "Engine 1 wins the match 3 - 0 after 3 games");
+        assert_eq!(series.summary(Language::German), "Engine 1 gewinnt das Match 3 - 0 nach 3 Spielen");
+    }
+}
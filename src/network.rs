@@ -0,0 +1,265 @@
+#![allow(clippy::needless_return)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::agent::{Agent, Turn};
+use crate::config::parse_player;
+use crate::notation::{board_to_notation, move_fmt, parse_board, parse_move};
+use crate::players::Player;
+use crate::record::player_name;
+use crate::state::{GameState, Move, BOARD_SIZE};
+use crate::tile::Tile::Empty;
+
+/// One line of the network protocol.
+///
+/// ```text
+/// hello order|chaos   sent by the host on connecting: the side the client plays
+/// board <board>       the position after the sender's last move, or the start position after hello
+/// move X A1           a move by the sender, always followed by board
+/// resign              the sender gives up the game
+/// rematch             the sender wants another game with sides swapped
+/// bye                 the sender does not want another game
+/// ```
+#[derive(Copy, Clone)]
+pub enum Message {
+    Hello(Player),
+    Board(GameState),
+    Move(Move),
+    Resign,
+    Rematch,
+    Bye,
+}
+
+impl Message {
+    pub fn to_line(self) -> String {
+        match self {
+            Message::Hello(player) => return format!("hello {}", player_name(&player).to_lowercase()),
+            Message::Board(game_state) => return format!("board {}", board_to_notation(&game_state)),
+            Message::Move(played_move) => return format!("move {}", move_fmt(&played_move)),
+            Message::Resign => return "resign".to_string(),
+            Message::Rematch => return "rematch".to_string(),
+            Message::Bye => return "bye".to_string()
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "hello" => return parse_player(argument).map(Message::Hello).map_err(|_| "hello needs order or chaos"),
+            "board" => return Ok(Message::Board(parse_board(argument)?)),
+            "move" => return Ok(Message::Move(parse_move(argument)?)),
+            "resign" => return Ok(Message::Resign),
+            "rematch" => return Ok(Message::Rematch),
+            "bye" => return Ok(Message::Bye),
+            _ => return Err("Unknown message")
+        }
+    }
+}
+
+/// A game between this program and one on another machine, seen from this side.
+///
+/// Reading and writing are locked separately, so that a message can be sent while an agent waits for the next one.
+#[derive(Clone)]
+pub struct NetworkSession {
+    pub local: Player,
+    pub start: GameState,
+    pub peer: String,
+    reader: Arc<Mutex<BufReader<TcpStream>>>,
+    writer: Arc<Mutex<TcpStream>>,
+}
+
+impl NetworkSession {
+    /// Waits for one player to connect on the given address and port. The host plays `local`.
+    pub fn serve(bind: &str, port: u16, local: Player) -> Result<Self, &'static str> {
+        let listener = TcpListener::bind((bind, port)).map_err(|_| "Could not listen on the port")?;
+        return Self::accept(&listener, local);
+    }
+
+    pub fn accept(listener: &TcpListener, local: Player) -> Result<Self, &'static str> {
+        let (stream, address) = listener.accept().map_err(|_| "Could not accept a connection")?;
+        let session = Self::new(stream, local, GameState::default_new(), address.to_string())?;
+        session.send(Message::Hello(local.opponent()))?;
+        session.send(Message::Board(session.start))?;
+        return Ok(session);
+    }
+
+    pub fn connect(address: &str) -> Result<Self, &'static str> {
+        let stream = TcpStream::connect(address).map_err(|_| "Could not connect")?;
+        let mut session = Self::new(stream, Player::Order, GameState::default_new(), address.to_string())?;
+        match session.receive()? {
+            Message::Hello(player) => session.local = player,
+            _ => return Err("The host did not say hello")
+        }
+        match session.receive()? {
+            Message::Board(game_state) => session.start = game_state,
+            _ => return Err("The host did not send the position")
+        }
+        return Ok(session);
+    }
+
+    fn new(stream: TcpStream, local: Player, start: GameState, peer: String) -> Result<Self, &'static str> {
+        let _ = stream.set_nodelay(true);
+        let reader = stream.try_clone().map_err(|_| "Could not set up the connection")?;
+        return Ok(Self {
+            local,
+            start,
+            peer,
+            reader: Arc::new(Mutex::new(BufReader::new(reader))),
+            writer: Arc::new(Mutex::new(stream)),
+        });
+    }
+
+    pub fn send(&self, message: Message) -> Result<(), &'static str> {
+        let mut writer = self.writer.lock().map_err(|_| "The connection broke")?;
+        return writeln!(writer, "{}", message.to_line()).and_then(|_| writer.flush()).map_err(|_| "The connection was closed");
+    }
+
+    pub fn receive(&self) -> Result<Message, &'static str> {
+        let mut reader = self.reader.lock().map_err(|_| "The connection broke")?;
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return Err("The connection was closed"),
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => return Message::parse(&line)
+            }
+        }
+    }
+
+    /// The agent standing in for the player on the other side.
+    pub fn remote_agent(&self) -> NetworkAgent {
+        return NetworkAgent { session: self.clone(), known_state: self.start };
+    }
+
+    /// Tells the other side whether this side wants another game and returns whether both do. Sides are swapped by the caller.
+    pub fn rematch(&self, wanted: bool) -> Result<bool, &'static str> {
+        self.send(if wanted { Message::Rematch } else { Message::Bye })?;
+        loop {
+            match self.receive()? {
+                Message::Rematch => return Ok(wanted),
+                Message::Bye => return Ok(false),
+                _ => continue
+            }
+        }
+    }
+
+    /// Prepares the next game after a rematch.
+    pub fn swap_sides(&mut self) {
+        self.local = self.local.opponent();
+        self.start = GameState::default_new();
+    }
+}
+
+/// The player on the other end of a `NetworkSession`. Before waiting for its move it sends the move played on this side.
+pub struct NetworkAgent {
+    session: NetworkSession,
+    known_state: GameState,
+}
+
+impl NetworkAgent {
+    /// Sends the move that turned the last position the peer knows about into `game_state`.
+    fn send_local_move(&mut self, game_state: &GameState) -> Result<(), &'static str> {
+        for row in 0..BOARD_SIZE {
+            for column in 0..BOARD_SIZE {
+                let tile = game_state.board[row][column];
+                if Empty.eq(&self.known_state.board[row][column]) && !Empty.eq(&tile) {
+                    self.session.send(Message::Move(((row, column), tile)))?;
+                    self.session.send(Message::Board(*game_state))?;
+                }
+            }
+        }
+        self.known_state = *game_state;
+        return Ok(());
+    }
+}
+
+impl Agent for NetworkAgent {
+    fn name(&self) -> String {
+        return format!("Network ({})", self.session.peer);
+    }
+
+    fn select_move(&mut self, game_state: &GameState, _time_limit: Option<Duration>) -> Result<Turn, &'static str> {
+        self.send_local_move(game_state)?;
+        loop {
+            match self.session.receive()? {
+                Message::Move((coordinates, tile)) => {
+                    let mut next_state = *game_state;
+                    next_state.play(coordinates, tile)?;
+                    match self.session.receive()? {
+                        Message::Board(peer_state) if peer_state.board == next_state.board => {}
+                        _ => return Err("The games are out of sync")
+                    }
+                    self.known_state = next_state;
+                    return Ok(Turn::Move((coordinates, tile)));
+                }
+                Message::Resign => return Ok(Turn::Resign),
+                Message::Bye => return Err("The opponent left"),
+                _ => continue
+            }
+        }
+    }
+
+    fn position_loaded(&mut self, _game_state: &GameState) -> Result<(), &'static str> {
+        return Err("Positions cannot be loaded in a network game");
+    }
+
+    fn game_over(&mut self, game_state: &GameState, resigned: Option<Player>) {
+        let _ = self.send_local_move(game_state);
+        if resigned == Some(self.session.local) {
+            let _ = self.session.send(Message::Resign);
+        }
+    }
+}
+
+#[cfg(test)]
+mod network_test {
+    use std::net::TcpListener;
+    use std::thread;
+    use crate::agent::{Agent, Turn};
+    use crate::network::{Message, NetworkSession};
+    use crate::players::Player::{Chaos, Order};
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_message_lines_round_trip() {
+        for line in ["hello chaos", "board XO..../....../....../....../....../......", "move X A1", "resign", "rematch", "bye"] {
+            assert_eq!(Message::parse(line).expect("").to_line(), line);
+        }
+        assert!(Message::parse("castle").is_err());
+    }
+
+    #[test]
+    fn test_game_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("");
+        let address = listener.local_addr().expect("").to_string();
+        let host = thread::spawn(move || {
+            let session = NetworkSession::accept(&listener, Order).expect("");
+            let mut remote = session.remote_agent();
+            let mut game_state = GameState::default_new();
+            game_state.play((0, 0), Red).expect("");
+            let Ok(Turn::Move(reply)) = remote.select_move(&game_state, None) else { panic!() };
+            assert_eq!(reply, ((1, 1), Blue));
+            game_state.play(reply.0, reply.1).expect("");
+            remote.game_over(&game_state, Some(Order));
+            assert_eq!(session.rematch(true), Ok(true));
+        });
+
+        let mut session = NetworkSession::connect(&address).expect("");
+        assert_eq!(session.local, Chaos);
+        let mut remote = session.remote_agent();
+        let mut game_state = session.start;
+        let Ok(Turn::Move(opening)) = remote.select_move(&game_state, None) else { panic!() };
+        assert_eq!(opening, ((0, 0), Red));
+        game_state.play(opening.0, opening.1).expect("");
+        game_state.play((1, 1), Blue).expect("");
+        assert_eq!(remote.select_move(&game_state, None), Ok(Turn::Resign));
+        assert_eq!(session.rematch(true), Ok(true));
+        session.swap_sides();
+        assert_eq!(session.local, Order);
+        host.join().expect("");
+    }
+}
@@ -0,0 +1,220 @@
+#![allow(clippy::needless_return)]
+
+use std::sync::OnceLock;
+use regex::Regex;
+use crate::players::Player::{Chaos, Order};
+use crate::state::{GameState, Move, BOARD_SIZE};
+use crate::tile::Tile;
+use crate::tile::Tile::{Blue, Empty, Red};
+
+/// Parses a move such as `X A1` or `O 5F`. Nothing but whitespace may surround it.
+pub fn parse_move(input: &str) -> Result<Move, &'static str> {
+    static REGULAR_EXPRESSIONS: OnceLock<[Regex; 2]> = OnceLock::new();
+    let [regular_expression_1, regular_expression_2] = REGULAR_EXPRESSIONS.get_or_init(|| [
+        Regex::new(r"^\s*([XOxo]) ([A-Fa-f])([1-6])\s*$").unwrap(),
+        Regex::new(r"^\s*([XOxo]) ([1-6])([A-Fa-f])\s*$").unwrap(),
+    ]);
+
+    let tile: &str;
+    let letter: &str;
+    let number: &str;
+    if let Some(captures) = regular_expression_1.captures(input) {
+        tile = captures.get(1).unwrap().as_str();
+        letter = captures.get(2).unwrap().as_str();
+        number = captures.get(3).unwrap().as_str();
+    } else if let Some(captures) = regular_expression_2.captures(input) {
+        tile = captures.get(1).unwrap().as_str();
+        number = captures.get(2).unwrap().as_str();
+        letter = captures.get(3).unwrap().as_str();
+    } else {
+        return Err("None of the Regular Expressions matched the input");
+    }
+
+    let coordinates = parse_coordinates(&format!("{}{}", letter, number))?;
+    return Ok((coordinates, parse_tile(tile)?));
+}
+
+/// Parses a cell such as `A1`, where the letter is the column and the number the row.
+pub fn parse_coordinates(input: &str) -> Result<(usize, usize), &'static str> {
+    let mut characters = input.trim().chars();
+    let column = match characters.next().map(|letter| letter.to_ascii_uppercase()) {
+        Some(letter @ 'A'..='F') => letter as usize - 'A' as usize,
+        _ => return Err("Not a valid column")
+    };
+    let row = match characters.next() {
+        Some(number @ '1'..='6') => number as usize - '1' as usize,
+        _ => return Err("Not a valid row")
+    };
+    if characters.next().is_some() {
+        return Err("Not a valid cell");
+    }
+    return Ok((row, column));
+}
+
+pub fn parse_tile(input: &str) -> Result<Tile, &'static str> {
+    if input.eq_ignore_ascii_case("X") {
+        return Ok(Red);
+    }
+    if input.eq_ignore_ascii_case("O") {
+        return Ok(Blue);
+    }
+    return Err("Not a valid pawn");
+}
+
+pub fn tile_letter(tile: &Tile) -> char {
+    match tile {
+        Red => 'X',
+        Blue => 'O',
+        Empty => '.'
+    }
+}
+
+pub fn coordinates_fmt(coordinates: &(usize, usize)) -> String {
+    let column = (b'A' + coordinates.1 as u8) as char;
+    return format!("{}{}", column, coordinates.0 + 1);
+}
+
+pub fn move_fmt(played_move: &Move) -> String {
+    return format!("{} {}", tile_letter(&played_move.1), coordinates_fmt(&played_move.0));
+}
+
+/// Writes the board as six rows of `X`, `O` and `.` separated by `/`, starting with row 1.
+pub fn board_to_notation(game_state: &GameState) -> String {
+    let rows: Vec<String> = game_state.board.iter()
+        .map(|row| row.iter().map(tile_letter).collect())
+        .collect();
+    return rows.join("/");
+}
+
+/// Reads a board written by `board_to_notation`. Order always moves first, so the turn player follows from the number of pieces.
+pub fn parse_board(input: &str) -> Result<GameState, &'static str> {
+    let rows: Vec<&str> = input.trim().split('/').collect();
+    if rows.len() != BOARD_SIZE {
+        return Err("A board needs six rows");
+    }
+    let mut game_state = GameState::default_new();
+    let mut pieces = 0;
+    for (row_index, row) in rows.iter().enumerate() {
+        let tiles: Vec<char> = row.chars().collect();
+        if tiles.len() != BOARD_SIZE {
+            return Err("Every row of a board needs six cells");
+        }
+        for (column_index, tile) in tiles.iter().enumerate() {
+            game_state.board[row_index][column_index] = match tile.to_ascii_uppercase() {
+                'X' => Red,
+                'O' => Blue,
+                '.' => Empty,
+                _ => return Err("Cells must be X, O or .")
+            };
+            if !Empty.eq(&game_state.board[row_index][column_index]) {
+                pieces += 1;
+            }
+        }
+    }
+    game_state.turn_player = if pieces % 2 == 0 { Order } else { Chaos };
+    return Ok(game_state);
+}
+
+/// Draws the board as six lines of `X`, `O` and `.`, optionally with the column letters above and the row numbers on the left:
+///
+/// ```text
+///   A B C D E F
+/// 1 X . . . . .
+/// 2 . O . . . .
+/// ```
+pub fn diagram_fmt(game_state: &GameState, labels: bool) -> String {
+    let mut diagram = String::new();
+    if labels {
+        diagram.push_str("  A B C D E F\n");
+    }
+    for (index, row) in game_state.board.iter().enumerate() {
+        if labels {
+            diagram.push_str(&format!("{} ", index + 1));
+        }
+        let cells: Vec<String> = row.iter().map(|tile| tile_letter(tile).to_string()).collect();
+        diagram.push_str(&cells.join(" "));
+        diagram.push('\n');
+    }
+    return diagram;
+}
+
+/// Reads a board drawn by `diagram_fmt`, with or without labels. Spaces between cells, blank lines and
+/// row numbers on either side are optional. As with `parse_board`, the turn player follows from the number of pieces.
+pub fn parse_diagram(input: &str) -> Result<GameState, &'static str> {
+    let mut rows = Vec::new();
+    for line in input.lines() {
+        let mut cells: Vec<char> = line.chars().filter(|character| !character.is_whitespace()).collect();
+        if cells.is_empty() || (cells.len() == BOARD_SIZE && cells.iter().all(|cell| ('A'..='F').contains(&cell.to_ascii_uppercase()))) {
+            continue;
+        }
+        let expected_label = char::from_digit(rows.len() as u32 + 1, 10);
+        if cells.len() > BOARD_SIZE && cells.first().is_some_and(char::is_ascii_digit) && cells.remove(0) != expected_label.unwrap_or_default() {
+            return Err("The rows of the diagram are out of order");
+        }
+        if cells.len() > BOARD_SIZE && cells.last() == expected_label.as_ref() {
+            cells.pop();
+        }
+        if cells.len() != BOARD_SIZE {
+            return Err("Every row of a diagram needs six cells");
+        }
+        rows.push(cells.into_iter().collect::<String>());
+    }
+    return parse_board(&rows.join("/")).map_err(|v| if v == "A board needs six rows" { "A diagram needs six rows" } else { v });
+}
+
+#[cfg(test)]
+mod notation_test {
+    use crate::notation::{board_to_notation, diagram_fmt, move_fmt, parse_board, parse_coordinates, parse_diagram, parse_move};
+    use crate::players::Player::Chaos;
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_parse_moves() {
+        assert_eq!(parse_move("X A1"), Ok(((0, 0), Red)));
+        assert_eq!(parse_move("O 5F\n"), Ok(((4, 5), Blue)));
+        assert_eq!(parse_move("o c3"), Ok(((2, 2), Blue)));
+        assert!(parse_move("X G1").is_err());
+        assert!(parse_move("zzX A1junk").is_err());
+        assert!(parse_move("foo X A1 bar").is_err());
+        assert!(parse_move("X A12").is_err());
+        assert!(parse_coordinates("A7").is_err());
+        assert_eq!(move_fmt(&((4, 5), Blue)), "O F5");
+    }
+
+    #[test]
+    fn test_board_round_trip() {
+        let mut game_state = GameState::default_new();
+        game_state.play((0, 0), Red).expect("");
+        game_state.play((5, 2), Blue).expect("");
+        game_state.play((3, 4), Red).expect("");
+        let notation = board_to_notation(&game_state);
+        assert_eq!(notation, "X...../....../....../....X./....../..O...");
+        let parsed = parse_board(&notation).expect("");
+        assert_eq!(parsed.board, game_state.board);
+        assert!(parsed.turn_player == Chaos);
+        assert!(parse_board("XX/..").is_err());
+    }
+
+    #[test]
+    fn test_diagram_round_trip() {
+        let game_state = parse_diagram("
+              A B C D E F
+            1 X . . . . .
+            2 . . . . . .
+            3 . . . . . .
+            4 . . . . X .
+            5 . . . . . .
+            6 . . O . . .
+        ").expect("");
+        assert_eq!(board_to_notation(&game_state), "X...../....../....../....X./....../..O...");
+        assert!(game_state.turn_player == Chaos);
+        assert_eq!(parse_diagram(&diagram_fmt(&game_state, true)).expect("").board, game_state.board);
+        assert_eq!(parse_diagram(&diagram_fmt(&game_state, false)).expect("").board, game_state.board);
+        assert_eq!(diagram_fmt(&game_state, false).lines().next(), Some("X . . . . ."));
+
+        assert_eq!(parse_diagram("X.....\n").err(), Some("A diagram needs six rows"));
+        assert_eq!(parse_diagram("1 X . . . . .\n3 . . . . . .").err(), Some("The rows of the diagram are out of order"));
+        assert_eq!(parse_diagram("X . . . .").err(), Some("Every row of a diagram needs six cells"));
+    }
+}
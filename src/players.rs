@@ -1,6 +1,17 @@
-#[derive(Eq, PartialEq, Copy, Clone)]
-pub enum Player {
-    Order,
-    Chaos,
-}
-
+use serde::{Deserialize, Serialize};
+
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Player {
+    Order,
+    Chaos,
+}
+
+
+impl Player {
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::Order => Player::Chaos,
+            Player::Chaos => Player::Order
+        }
+    }
+}
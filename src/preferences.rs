@@ -0,0 +1,192 @@
+#![allow(clippy::needless_return)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use structopt::clap::ArgMatches;
+use crate::config::{parse_player, Options};
+use crate::record::player_name;
+use crate::theme::Theme;
+
+/// Defaults kept between runs in a TOML file. Anything given on the command line wins over the file.
+///
+/// ```toml
+/// terminal = true
+/// tile_size = 90.0
+/// computer = "chaos"
+/// difficulty = "expert"
+/// time_control = "300+5"
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub terminal: Option<bool>,
+    pub disable_emoji: Option<bool>,
+    pub disable_color_and_emoji: Option<bool>,
+    pub force_color: Option<bool>,
+    /// Size of a board cell in the window, in points.
+    pub tile_size: Option<f32>,
+    pub computer: Option<String>,
+    pub difficulty: Option<String>,
+    pub order_engine: Option<String>,
+    pub chaos_engine: Option<String>,
+    pub move_time: Option<u64>,
+    pub time_control: Option<String>,
+    /// A language code such as `de`.
+    pub language: Option<String>,
+    /// A built-in theme or one of `themes`.
+    pub theme: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub themes: BTreeMap<String, Theme>,
+}
+
+/// The file given with --config, or config.toml in the order_and_chaos folder of the user's configuration directory.
+pub fn preferences_path(options: &Options) -> Option<PathBuf> {
+    if let Some(path) = &options.config {
+        return Some(PathBuf::from(path));
+    }
+    return dirs::config_dir().map(|directory| directory.join("order_and_chaos").join("config.toml"));
+}
+
+impl Preferences {
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        return toml::from_str(text).map_err(|_| "The configuration file is not valid TOML");
+    }
+
+    /// Loads the file, or no preferences at all if there is none yet.
+    pub fn load(path: &PathBuf) -> Result<Self, &'static str> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).map_err(|_| "Could not read the configuration file")?;
+        return Self::parse(&text);
+    }
+
+    pub fn save(&self, path: &PathBuf) -> Result<(), &'static str> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|_| "Could not create the configuration directory")?;
+        }
+        let text = toml::to_string(self).map_err(|_| "Could not write the preferences")?;
+        return fs::write(path, text).map_err(|_| "Could not write the configuration file");
+    }
+
+    /// Fills in every option that was not given on the command line.
+    pub fn apply(&self, options: &mut Options, matches: &ArgMatches) -> Result<(), String> {
+        let unset = |name: &str| matches.occurrences_of(name) == 0;
+        if let (true, Some(value)) = (unset("terminal") && unset("mode"), self.terminal) {
+            options.disable_gui = value;
+        }
+        if let (true, Some(value)) = (unset("disable_emoji"), self.disable_emoji) {
+            options.disable_emoji = value;
+        }
+        if let (true, Some(value)) = (unset("disable_color_and_emoji"), self.disable_color_and_emoji) {
+            options.disable_color_and_emoji = value;
+        }
+        if let (true, Some(value)) = (unset("force_color"), self.force_color) {
+            options.force_color = value;
+        }
+        if let (true, Some(value)) = (unset("tile_size"), self.tile_size) {
+            options.tile_size = value;
+        }
+        if let (true, Some(value)) = (unset("computer"), &self.computer) {
+            options.computer = Some(parse_player(value)?);
+        }
+        if let (true, Some(value)) = (unset("difficulty"), &self.difficulty) {
+            options.difficulty = value.parse()?;
+        }
+        if let (true, Some(value)) = (unset("order_engine"), &self.order_engine) {
+            options.order_engine = Some(value.clone());
+        }
+        if let (true, Some(value)) = (unset("chaos_engine"), &self.chaos_engine) {
+            options.chaos_engine = Some(value.clone());
+        }
+        if let (true, Some(value)) = (unset("move_time"), self.move_time) {
+            options.move_time = value;
+        }
+        if let (true, Some(value)) = (unset("time_control"), &self.time_control) {
+            options.time_control = Some(value.parse()?);
+        }
+        if let (true, Some(value)) = (unset("theme"), &self.theme) {
+            options.theme_name = value.clone();
+        }
+        if let (true, Some(value)) = (unset("language"), &self.language) {
+            options.language_name = Some(value.clone());
+        }
+        return Ok(());
+    }
+
+    /// A theme defined in the file, which may replace a built-in one, or else the built-in theme of that name.
+    pub fn theme_named(&self, name: &str) -> Option<Theme> {
+        return self.themes.get(name).copied().or_else(|| Theme::built_in(name));
+    }
+
+    /// Takes over the settings the window lets the player change, where they differ from how the window started.
+    pub fn remember_window_changes(&mut self, started: &Options, options: &Options) {
+        if options.tile_size != started.tile_size {
+            self.tile_size = Some(options.tile_size);
+        }
+        if options.computer != started.computer {
+            self.computer = options.computer.as_ref().map(|player| player_name(player).to_lowercase());
+        }
+        if options.difficulty != started.difficulty {
+            self.difficulty = Some(options.difficulty.name().to_string());
+        }
+    }
+}
+
+/// Writes the settings changed in the window back to the configuration file.
+pub fn save_window_preferences(started: &Options, options: &Options) -> Result<(), &'static str> {
+    let path = preferences_path(options).ok_or("There is no configuration directory")?;
+    let mut preferences = Preferences::load(&path)?;
+    let before = preferences.clone();
+    preferences.remember_window_changes(started, options);
+    if preferences == before {
+        return Ok(());
+    }
+    return preferences.save(&path);
+}
+
+#[cfg(test)]
+mod preferences_test {
+    use structopt::StructOpt;
+    use crate::cli::Cli;
+    use crate::config::Options;
+    use crate::engine::Difficulty;
+    use crate::players::Player::Chaos;
+    use crate::preferences::Preferences;
+
+    #[test]
+    fn test_command_line_wins_over_the_file() {
+        let preferences = Preferences::parse("terminal = true\ntile_size = 90.0\ncomputer = \"chaos\"\ndifficulty = \"expert\"\ntime_control = \"300+5\"\n").expect("");
+        let matches = Cli::clap().get_matches_from(["order_and_chaos", "play", "--difficulty", "beginner"]);
+        let mut options = Cli::from_clap(&matches).command().options();
+        let matches = matches.subcommand_matches("play").expect("");
+        preferences.apply(&mut options, matches).expect("");
+        assert!(options.disable_gui);
+        assert_eq!(options.tile_size, 90.0);
+        assert_eq!(options.computer, Some(Chaos));
+        assert_eq!(options.difficulty, Difficulty::Beginner);
+        assert_eq!(options.time_control.map(|control| control.to_string()), Some("300+5".to_string()));
+
+        assert!(Preferences::parse("difficulty = 3").is_err());
+        assert!(Preferences { difficulty: Some("grandmaster".to_string()), ..Preferences::default() }.apply(&mut options, matches).is_ok());
+        assert!(Preferences { computer: Some("nobody".to_string()), ..Preferences::default() }.apply(&mut options, matches).is_err());
+    }
+
+    #[test]
+    fn test_window_changes_are_written_back() {
+        let started = Options::default_options();
+        let mut options = started.clone();
+        options.tile_size = 80.0;
+        options.difficulty = Difficulty::Expert;
+        let mut preferences = Preferences { move_time: Some(500), ..Preferences::default() };
+        preferences.remember_window_changes(&started, &options);
+        let text = toml::to_string(&preferences).expect("");
+        let reloaded = Preferences::parse(&text).expect("");
+        assert_eq!(reloaded.tile_size, Some(80.0));
+        assert_eq!(reloaded.difficulty.as_deref(), Some("expert"));
+        assert_eq!(reloaded.move_time, Some(500));
+        assert_eq!(reloaded.computer, None);
+    }
+}
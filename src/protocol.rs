@@ -0,0 +1,258 @@
+#![allow(clippy::needless_return)]
+
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::engine::{Difficulty, Engine};
+use crate::notation::{board_to_notation, move_fmt, parse_board, parse_move};
+use crate::state::{GameState, Move};
+
+/// One engine conversation, driven line by line.
+///
+/// Commands understood:
+/// `isready`, `newgame`, `position startpos|<board> [moves X A1 O B2 ...]`, `play X A1`,
+/// `difficulty <level>`, `go [movetime <ms>] [depth <n>]`, `board` and `quit`.
+/// `go` is answered with `bestmove X A1` (or `bestmove none` on a full board), errors with `error <message>`.
+pub struct EngineSession {
+    game_state: GameState,
+    difficulty: Difficulty,
+    rng: StdRng,
+}
+
+impl EngineSession {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self { game_state: GameState::default_new(), difficulty, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Answers one command. Returns `None` once the session should end.
+    pub fn handle(&mut self, line: &str) -> Option<Vec<String>> {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => return Some(Vec::new())
+        };
+        let arguments: Vec<&str> = words.collect();
+        let result = match command {
+            "isready" => Ok(vec!["readyok".to_string()]),
+            "newgame" => {
+                self.game_state = GameState::default_new();
+                Ok(Vec::new())
+            }
+            "position" => self.set_position(&arguments).map(|_| Vec::new()),
+            "play" => play_moves(&mut self.game_state, &arguments).map(|_| Vec::new()),
+            "difficulty" => match arguments.join(" ").parse() {
+                Ok(difficulty) => {
+                    self.difficulty = difficulty;
+                    Ok(Vec::new())
+                }
+                Err(_) => Err("Unknown difficulty")
+            },
+            "go" => self.go(&arguments).map(|answer| vec![answer]),
+            "board" => Ok(vec![format!("board {}", board_to_notation(&self.game_state))]),
+            "quit" => return None,
+            _ => Err("Unknown command")
+        };
+        match result {
+            Ok(answers) => return Some(answers),
+            Err(message) => return Some(vec![format!("error {}", message)])
+        }
+    }
+
+    fn set_position(&mut self, arguments: &[&str]) -> Result<(), &'static str> {
+        let mut game_state = match arguments.first() {
+            Some(&"startpos") => GameState::default_new(),
+            Some(board) => parse_board(board)?,
+            None => return Err("position needs startpos or a board")
+        };
+        match arguments.get(1) {
+            Some(&"moves") => play_moves(&mut game_state, &arguments[2..])?,
+            Some(_) => return Err("Expected moves after the position"),
+            None => {}
+        }
+        self.game_state = game_state;
+        return Ok(());
+    }
+
+    fn go(&mut self, arguments: &[&str]) -> Result<String, &'static str> {
+        let mut engine = Engine::new(self.difficulty);
+        let mut index = 0;
+        while index < arguments.len() {
+            let value = arguments.get(index + 1).and_then(|value| value.parse::<u64>().ok());
+            match (arguments[index], value) {
+                ("movetime", Some(milliseconds)) => engine.time_limit = Some(Duration::from_millis(milliseconds)),
+                ("depth", Some(depth)) => engine.depth = depth as usize,
+                _ => return Err("go accepts movetime <ms> and depth <n>")
+            }
+            index += 2;
+        }
+        match engine.best_move(&self.game_state, &mut self.rng) {
+            Some(best_move) => return Ok(format!("bestmove {}", move_fmt(&best_move))),
+            None => return Ok("bestmove none".to_string())
+        }
+    }
+}
+
+fn play_moves(game_state: &mut GameState, words: &[&str]) -> Result<(), &'static str> {
+    if !words.len().is_multiple_of(2) {
+        return Err("Moves are written as a pawn and a cell, e.g. X A1");
+    }
+    for played_move in words.chunks(2) {
+        let (coordinates, tile) = parse_move(&played_move.join(" "))?;
+        game_state.play(coordinates, tile)?;
+    }
+    return Ok(());
+}
+
+/// Speaks the engine protocol on stdin and stdout until `quit` or the end of input.
+pub fn run_engine_protocol(difficulty: Difficulty, seed: u64) {
+    let mut session = EngineSession::new(difficulty, seed);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return
+        };
+        match session.handle(&line) {
+            Some(answers) => {
+                for answer in answers {
+                    let _ = writeln!(stdout, "{}", answer);
+                }
+                let _ = stdout.flush();
+            }
+            None => return
+        }
+    }
+}
+
+/// How long an engine may take to answer `isready` after it starts.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long past its move time an engine may take to send its move.
+const ANSWER_GRACE: Duration = Duration::from_secs(2);
+/// How long an engine may take to exit after `quit` before it is killed.
+const QUIT_GRACE: Duration = Duration::from_secs(1);
+
+/// An engine running in a child process that speaks the engine protocol.
+/// Its answers are read on a thread of their own so that waiting for one can give up.
+pub struct ExternalEngine {
+    child: Child,
+    input: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl ExternalEngine {
+    pub fn start(command: &str) -> Result<Self, &'static str> {
+        let mut engine = Self::spawn(command)?;
+        engine.send("isready")?;
+        engine.wait_for("readyok", Instant::now() + STARTUP_TIMEOUT)?;
+        return Ok(engine);
+    }
+
+    fn spawn(command: &str) -> Result<Self, &'static str> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("The engine command is empty")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|_| "Could not start the engine")?;
+        let input = child.stdin.take().ok_or("Could not talk to the engine")?;
+        let output = BufReader::new(child.stdout.take().ok_or("Could not listen to the engine")?);
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in output.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        return Ok(Self { child, input, lines });
+    }
+
+    pub fn best_move(&mut self, game_state: &GameState, move_time: Duration) -> Result<Move, &'static str> {
+        self.send(&format!("position {}", board_to_notation(game_state)))?;
+        self.send(&format!("go movetime {}", move_time.as_millis()))?;
+        let answer = self.wait_for("bestmove", Instant::now() + move_time + ANSWER_GRACE)?;
+        return parse_move(answer.trim_start_matches("bestmove"));
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), &'static str> {
+        writeln!(self.input, "{}", command).map_err(|_| "The engine stopped listening")?;
+        return self.input.flush().map_err(|_| "The engine stopped listening");
+    }
+
+    fn wait_for(&mut self, prefix: &str, deadline: Instant) -> Result<String, &'static str> {
+        loop {
+            let line = match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err("The engine did not answer in time"),
+                Err(RecvTimeoutError::Disconnected) => return Err("The engine stopped answering")
+            };
+            if line.starts_with(prefix) {
+                return Ok(line.trim().to_string());
+            }
+            if line.starts_with("error") {
+                return Err("The engine reported an error");
+            }
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + QUIT_GRACE;
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                _ => return
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod protocol_test {
+    use std::time::{Duration, Instant};
+    use crate::engine::Difficulty;
+    use crate::protocol::{EngineSession, ExternalEngine, QUIT_GRACE};
+
+    #[test]
+    fn test_session() {
+        let mut session = EngineSession::new(Difficulty::Expert, 7);
+        assert_eq!(session.handle("isready"), Some(vec!["readyok".to_string()]));
+        assert_eq!(session.handle("position startpos moves X A1 O F6 X B1 O F5 X C1 O E6 X D1"), Some(vec![]));
+        assert_eq!(session.handle("board"), Some(vec!["board XXXX../....../....../....../.....O/....OO".to_string()]));
+        let answer = session.handle("go movetime 500 depth 2").expect("").join("");
+        assert!(answer == "bestmove O E1" || answer == "bestmove X F1");
+        assert_eq!(session.handle("play X A1"), Some(vec!["error Space Already Occupied".to_string()]));
+        assert_eq!(session.handle("quit"), None);
+    }
+
+    #[test]
+    fn test_order_finds_the_win() {
+        let mut session = EngineSession::new(Difficulty::Expert, 7);
+        session.handle("position XXXX../....../....../....../....../....OO");
+        assert_eq!(session.handle("go depth 1"), Some(vec!["bestmove X E1".to_string()]));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_silent_engine_is_given_up_on() {
+        // `cat` only repeats what it is told, so it never answers and ignores `quit`.
+        let mut engine = ExternalEngine::spawn("cat").expect("");
+        engine.send("go movetime 10").expect("");
+        assert_eq!(engine.wait_for("bestmove", Instant::now() + Duration::from_millis(100)), Err("The engine did not answer in time"));
+        let started = Instant::now();
+        drop(engine);
+        assert!(started.elapsed() < QUIT_GRACE + Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,323 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::time::Duration;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::engine::{legal_moves, Difficulty, Engine};
+use crate::notation::{board_to_notation, move_fmt, parse_board, parse_move};
+use crate::players::Player::{Chaos, Order};
+use crate::state::{GameState, Move};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PuzzleKind {
+    /// Order, to move, can force five in a row within this many of its own moves.
+    OrderWinsIn(usize),
+    /// Chaos, to move, has exactly one move that stops every immediate threat.
+    ChaosSaves,
+}
+
+impl PuzzleKind {
+    fn name(&self) -> String {
+        match self {
+            PuzzleKind::OrderWinsIn(moves) => format!("order-win-{}", moves),
+            PuzzleKind::ChaosSaves => "chaos-save".to_string()
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, &'static str> {
+        if name == "chaos-save" {
+            return Ok(PuzzleKind::ChaosSaves);
+        }
+        match name.strip_prefix("order-win-").and_then(|moves| moves.parse().ok()) {
+            Some(moves) if moves > 0 => return Ok(PuzzleKind::OrderWinsIn(moves)),
+            _ => return Err("Unknown puzzle kind")
+        }
+    }
+}
+
+/// A position with exactly one correct move, together with how often it has been tried and solved.
+#[derive(Copy, Clone)]
+pub struct Puzzle {
+    pub position: GameState,
+    pub kind: PuzzleKind,
+    pub solution: Move,
+    pub attempts: u32,
+    pub solved: u32,
+}
+
+impl Puzzle {
+    /// Checks an answer and counts the attempt.
+    pub fn answer(&mut self, answer: Move) -> bool {
+        self.attempts += 1;
+        if answer == self.solution {
+            self.solved += 1;
+            return true;
+        }
+        return false;
+    }
+
+    /// Whether the solver still finds exactly the stored solution.
+    pub fn verify(&self) -> bool {
+        return solutions(&self.position, self.kind) == vec![self.solution];
+    }
+
+    fn to_line(self) -> String {
+        return format!("{} {} {} {} {}", board_to_notation(&self.position), self.kind.name(), move_fmt(&self.solution), self.attempts, self.solved);
+    }
+
+    fn parse(line: &str) -> Result<Self, &'static str> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() != 6 {
+            return Err("A puzzle line needs a board, a kind, a solution, attempts and solves");
+        }
+        return Ok(Self {
+            position: parse_board(words[0])?,
+            kind: PuzzleKind::parse(words[1])?,
+            solution: parse_move(&format!("{} {}", words[2], words[3]))?,
+            attempts: words[4].parse().map_err(|_| "Attempts must be a whole number")?,
+            solved: words[5].parse().map_err(|_| "Solves must be a whole number")?,
+        });
+    }
+}
+
+/// Every move that solves the position as a puzzle of the given kind.
+pub fn solutions(game_state: &GameState, kind: PuzzleKind) -> Vec<Move> {
+    if game_state.is_in_order() || !game_state.can_order_win() {
+        return Vec::new();
+    }
+    match kind {
+        PuzzleKind::OrderWinsIn(moves) => {
+            if !Order.eq(&game_state.turn_player) {
+                return Vec::new();
+            }
+            return legal_moves(game_state).into_iter().filter(|candidate| {
+                let mut next_state = *game_state;
+                let _ = next_state.play(candidate.0, candidate.1);
+                next_state.is_in_order() || (moves > 1 && chaos_cannot_escape(&next_state, moves - 1))
+            }).collect();
+        }
+        PuzzleKind::ChaosSaves => {
+            if !Chaos.eq(&game_state.turn_player) {
+                return Vec::new();
+            }
+            return game_state.threats().saving_moves;
+        }
+    }
+}
+
+/// Whether Order, to move, wins within `moves` of its own moves whatever Chaos does.
+pub fn order_wins_within(game_state: &GameState, moves: usize) -> bool {
+    if moves == 0 || !game_state.can_order_win() {
+        return false;
+    }
+    if !game_state.winning_moves().is_empty() {
+        return true;
+    }
+    if moves == 1 {
+        return false;
+    }
+    for candidate in legal_moves(game_state) {
+        let mut next_state = *game_state;
+        let _ = next_state.play(candidate.0, candidate.1);
+        if chaos_cannot_escape(&next_state, moves - 1) {
+            return true;
+        }
+    }
+    return false;
+}
+
+/// Whether every Chaos reply still lets Order win within `moves` of its own moves.
+fn chaos_cannot_escape(game_state: &GameState, moves: usize) -> bool {
+    if !game_state.can_order_win() {
+        return false;
+    }
+    let threats = game_state.threats();
+    if moves == 1 {
+        return !threats.can_chaos_neutralise_all();
+    }
+    for reply in legal_moves(game_state) {
+        let mut next_state = *game_state;
+        let _ = next_state.play(reply.0, reply.1);
+        if !next_state.is_in_order() && !order_wins_within(&next_state, moves) {
+            return false;
+        }
+    }
+    return true;
+}
+
+/// What a search for a forced Order win finds in a position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    /// How many of its own moves Order needs to force five in a row, if it can within the searched depth.
+    pub order_wins_in: Option<usize>,
+    /// With Order to move, the moves that force the win. With Chaos to move, the moves that hold out for the
+    /// whole search, or none if Order wins whatever Chaos plays.
+    pub moves: Vec<Move>,
+}
+
+/// Searches up to `depth` Order moves ahead for the quickest forced win.
+pub fn solve(game_state: &GameState, depth: usize) -> Result<Solution, &'static str> {
+    if game_state.is_in_order() || !game_state.can_order_win() {
+        return Err("The game is already over");
+    }
+    if Order.eq(&game_state.turn_player) {
+        for moves in 1..=depth {
+            let winning = solutions(game_state, PuzzleKind::OrderWinsIn(moves));
+            if !winning.is_empty() {
+                return Ok(Solution { order_wins_in: Some(moves), moves: winning });
+            }
+        }
+        return Ok(Solution { order_wins_in: None, moves: Vec::new() });
+    }
+    let mut holding = legal_moves(game_state);
+    for moves in 1..=depth {
+        holding.retain(|reply| {
+            let mut next_state = *game_state;
+            let _ = next_state.play(reply.0, reply.1);
+            !next_state.is_in_order() && !order_wins_within(&next_state, moves)
+        });
+        if holding.is_empty() {
+            return Ok(Solution { order_wins_in: Some(moves), moves: Vec::new() });
+        }
+    }
+    return Ok(Solution { order_wins_in: None, moves: holding });
+}
+
+/// Looks for a puzzle with a unique answer in the given position.
+pub fn find_puzzle(game_state: &GameState) -> Option<Puzzle> {
+    let kinds = match game_state.turn_player {
+        Order => vec![PuzzleKind::OrderWinsIn(2), PuzzleKind::OrderWinsIn(1)],
+        Chaos => vec![PuzzleKind::ChaosSaves]
+    };
+    for kind in kinds {
+        if let PuzzleKind::OrderWinsIn(moves) = kind {
+            if moves > 1 && order_wins_within(game_state, moves - 1) {
+                continue;
+            }
+        }
+        let found = solutions(game_state, kind);
+        if found.len() == 1 {
+            return Some(Puzzle { position: *game_state, kind, solution: found[0], attempts: 0, solved: 0 });
+        }
+    }
+    return None;
+}
+
+/// Plays engine games against itself and collects up to `count` puzzles from the positions that come up.
+pub fn generate_puzzles(count: usize, max_games: usize, seed: u64) -> Vec<Puzzle> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut engine = Engine::new(Difficulty::Casual);
+    engine.time_limit = Some(Duration::from_millis(20));
+    let mut puzzles: Vec<Puzzle> = Vec::new();
+    for _ in 0..max_games {
+        let mut game_state = GameState::default_new();
+        while game_state.can_order_win() && !game_state.is_in_order() && puzzles.len() < count {
+            if let Some(puzzle) = find_puzzle(&game_state) {
+                if !puzzles.iter().any(|known| known.position.board == puzzle.position.board) {
+                    puzzles.push(puzzle);
+                }
+            }
+            match engine.best_move(&game_state, &mut rng) {
+                Some((coordinates, tile)) => {
+                    let _ = game_state.play(coordinates, tile);
+                }
+                None => break
+            }
+        }
+        if puzzles.len() >= count {
+            break;
+        }
+    }
+    return puzzles;
+}
+
+pub fn save_puzzles(puzzles: &[Puzzle], path: &str) -> Result<(), &'static str> {
+    let lines: Vec<String> = puzzles.iter().map(|puzzle| puzzle.to_line()).collect();
+    return fs::write(path, lines.join("\n") + "\n").map_err(|_| "Could not write the puzzle file");
+}
+
+pub fn load_puzzles(path: &str) -> Result<Vec<Puzzle>, &'static str> {
+    let text = fs::read_to_string(path).map_err(|_| "Could not read the puzzle file")?;
+    let mut puzzles = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let puzzle = Puzzle::parse(line)?;
+        if !puzzle.verify() {
+            return Err("A puzzle in the file does not have its solution as the only answer");
+        }
+        puzzles.push(puzzle);
+    }
+    return Ok(puzzles);
+}
+
+/// The share of all recorded attempts that were solved, from 0 to 100.
+pub fn success_rate(puzzles: &[Puzzle]) -> u32 {
+    let attempts: u32 = puzzles.iter().map(|puzzle| puzzle.attempts).sum();
+    let solved: u32 = puzzles.iter().map(|puzzle| puzzle.solved).sum();
+    if attempts == 0 {
+        return 0;
+    }
+    return solved * 100 / attempts;
+}
+
+#[cfg(test)]
+mod puzzle_test {
+    use crate::notation::parse_board;
+    use crate::puzzle::{find_puzzle, order_wins_within, solutions, solve, success_rate, Puzzle, PuzzleKind};
+    use crate::tile::Tile::{Blue, Red};
+
+    #[test]
+    fn test_order_wins_in_one() {
+        let game_state = parse_board("XXXX../....../....../....../....../....OO").expect("");
+        assert_eq!(solutions(&game_state, PuzzleKind::OrderWinsIn(1)), vec![((0, 4), Red)]);
+        let puzzle = find_puzzle(&game_state).expect("");
+        assert_eq!(puzzle.kind, PuzzleKind::OrderWinsIn(1));
+        assert!(puzzle.verify());
+    }
+
+    #[test]
+    fn test_order_wins_in_two() {
+        let game_state = parse_board("....../.XXX../....../....../O.O.../O.....").expect("");
+        assert!(!order_wins_within(&game_state, 1));
+        assert!(order_wins_within(&game_state, 2));
+        assert!(solutions(&game_state, PuzzleKind::OrderWinsIn(2)).contains(&((1, 4), Red)));
+    }
+
+    #[test]
+    fn test_chaos_saves() {
+        let game_state = parse_board("XXXX../....../....../....../....../.....O").expect("");
+        let found = solutions(&game_state, PuzzleKind::ChaosSaves);
+        assert!(found.contains(&((0, 4), Blue)));
+        assert!(found.contains(&((0, 5), Red)));
+    }
+
+    #[test]
+    fn test_solve() {
+        let game_state = parse_board("....../.XXX../....../....../O.O.../O.....").expect("");
+        let solution = solve(&game_state, 2).expect("");
+        assert_eq!(solution.order_wins_in, Some(2));
+        assert!(solution.moves.contains(&((1, 4), Red)));
+        assert_eq!(solve(&game_state, 1).expect("").order_wins_in, None);
+
+        let game_state = parse_board("XXXX../....../....../....../....../.....O").expect("");
+        let solution = solve(&game_state, 1).expect("");
+        assert_eq!(solution.order_wins_in, None);
+        assert!(solution.moves.contains(&((0, 4), Blue)));
+        assert!(!solution.moves.contains(&((3, 3), Blue)));
+        assert!(solve(&parse_board("XXXXX./....../....../....../....../.....O").expect(""), 2).is_err());
+    }
+
+    #[test]
+    fn test_puzzle_lines_round_trip() {
+        let mut puzzle = find_puzzle(&parse_board("XXXX../....../....../....../....../....OO").expect("")).expect("");
+        assert!(!puzzle.answer(((0, 5), Red)));
+        assert!(puzzle.answer(((0, 4), Red)));
+        let parsed = Puzzle::parse(&puzzle.to_line()).expect("");
+        assert_eq!(parsed.solution, puzzle.solution);
+        assert_eq!((parsed.attempts, parsed.solved), (2, 1));
+        assert_eq!(success_rate(&[parsed]), 50);
+    }
+}
@@ -0,0 +1,231 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use crate::json::parse_json;
+use crate::notation::{board_to_notation, move_fmt, parse_board, parse_move};
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::state::{GameState, Move};
+
+/// Everything needed to replay a game: the moves in order plus tags such as the players, the seed and the result.
+///
+/// Records are stored as text, one `[Name "value"]` tag per line, a blank line and then one move per line:
+///
+/// ```text
+/// [Order "Human"]
+/// [Chaos "Random"]
+/// [Seed "1234"]
+/// [Result "Chaos"]
+///
+/// X A1
+/// O F6
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameRecord {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<Move>,
+}
+
+impl GameRecord {
+    pub fn new(order_name: &str, chaos_name: &str, seed: u64) -> Self {
+        let mut record = Self::default();
+        record.set_tag("Order", order_name);
+        record.set_tag("Chaos", chaos_name);
+        record.set_tag("Seed", &seed.to_string());
+        record.set_tag("Result", "*");
+        return record;
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str());
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string()))
+        }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        return self.tag("Seed").and_then(|seed| seed.parse().ok());
+    }
+
+    pub fn set_winner(&mut self, winner: Player) {
+        self.set_tag("Result", player_name(&winner));
+    }
+
+    /// Records that `loser` gave up the game.
+    pub fn set_resignation(&mut self, loser: Player) {
+        self.set_winner(loser.opponent());
+        self.set_tag("Termination", "Resignation");
+    }
+
+    /// Records that `loser` ran out of time.
+    pub fn set_time_forfeit(&mut self, loser: Player) {
+        self.set_winner(loser.opponent());
+        self.set_tag("Termination", "Time forfeit");
+    }
+
+    /// Records that `loser` could not go on, because its program failed or the connection to it was lost.
+    pub fn set_abandonment(&mut self, loser: Player) {
+        self.set_winner(loser.opponent());
+        self.set_tag("Termination", "Abandoned");
+    }
+
+    /// The position the game started from: the empty board, or the board in the `Position` tag if the game was set up.
+    pub fn initial_state(&self) -> Result<GameState, &'static str> {
+        match self.tag("Position") {
+            Some(board) => return parse_board(board),
+            None => return Ok(GameState::default_new())
+        }
+    }
+
+    /// Starts the game again from `game_state`, dropping the moves played so far.
+    pub fn set_position(&mut self, game_state: &GameState) {
+        self.set_tag("Position", &board_to_notation(game_state));
+        self.moves.clear();
+    }
+
+    /// The position after the first `plies` moves.
+    pub fn state_after(&self, plies: usize) -> Result<GameState, &'static str> {
+        let mut game_state = self.initial_state()?;
+        for (coordinates, tile) in self.moves.iter().take(plies) {
+            game_state.play(*coordinates, *tile)?;
+        }
+        return Ok(game_state);
+    }
+
+    pub fn final_state(&self) -> Result<GameState, &'static str> {
+        return self.state_after(self.moves.len());
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, value) in &self.tags {
+            text.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        text.push('\n');
+        for played_move in &self.moves {
+            text.push_str(&move_fmt(played_move));
+            text.push('\n');
+        }
+        return text;
+    }
+
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut record = Self::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                let (name, value) = parse_tag(line)?;
+                record.set_tag(name, value);
+            } else {
+                record.moves.push(parse_move(line)?);
+            }
+        }
+        record.final_state()?;
+        return Ok(record);
+    }
+
+    /// Parses several records written one after another. A tag after a move, or a tag that is already set, starts the next record.
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, &'static str> {
+        let mut records = Vec::new();
+        let mut record = Self::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                let (name, value) = parse_tag(line)?;
+                if !record.moves.is_empty() || record.tag(name).is_some() {
+                    record.final_state()?;
+                    records.push(record);
+                    record = Self::default();
+                }
+                record.set_tag(name, value);
+            } else {
+                record.moves.push(parse_move(line)?);
+            }
+        }
+        if record != Self::default() {
+            record.final_state()?;
+            records.push(record);
+        }
+        return Ok(records);
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), &'static str> {
+        return fs::write(path, self.to_text()).map_err(|_| "Could not write the game record");
+    }
+
+    /// Loads a game record, or a game exported as JSON.
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        let text = fs::read_to_string(path).map_err(|_| "Could not read the game record")?;
+        if text.trim_start().starts_with('{') {
+            return parse_json(&text);
+        }
+        return Self::parse(&text);
+    }
+}
+
+pub fn player_name(player: &Player) -> &'static str {
+    match player {
+        Order => "Order",
+        Chaos => "Chaos"
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(&str, &str), &'static str> {
+    let inner = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')).ok_or("A tag must be enclosed in []")?;
+    let (name, value) = inner.split_once(' ').ok_or("A tag needs a name and a value")?;
+    let value = value.trim().strip_prefix('"').and_then(|value| value.strip_suffix('"')).ok_or("A tag value must be quoted")?;
+    return Ok((name, value));
+}
+
+#[cfg(test)]
+mod record_test {
+    use crate::players::Player::Chaos;
+    use crate::record::GameRecord;
+    use crate::state::GameState;
+    use crate::tile::Tile::{Blue, Empty, Red};
+
+    #[test]
+    fn test_round_trip() {
+        let mut record = GameRecord::new("Human", "Random", 42);
+        record.moves.push(((0, 0), Red));
+        record.moves.push(((5, 5), Blue));
+        record.set_winner(Chaos);
+        let text = record.to_text();
+        assert_eq!(text, "[Order \"Human\"]\n[Chaos \"Random\"]\n[Seed \"42\"]\n[Result \"Chaos\"]\n\nX A1\nO F6\n");
+        let parsed = GameRecord::parse(&text).expect("");
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.seed(), Some(42));
+        assert_eq!(parsed.tag("Result"), Some("Chaos"));
+    }
+
+    #[test]
+    fn test_set_up_position() {
+        let mut record = GameRecord::new("Human", "Random", 42);
+        record.moves.push(((0, 0), Red));
+        let mut position = GameState::default_new();
+        position.play((2, 2), Blue).expect("");
+        record.set_position(&position);
+        record.moves.push(((3, 3), Red));
+        let parsed = GameRecord::parse(&record.to_text()).expect("");
+        assert_eq!(parsed.tag("Position"), Some("....../....../..O.../....../....../......"));
+        assert_eq!(parsed.final_state().expect("").tile_at((2, 2)), Blue);
+        assert_eq!(parsed.final_state().expect("").tile_at((0, 0)), Empty);
+        assert!(GameRecord::parse("[Position \"X...../....../....../....../....../......\"]\nX A1\n").is_err());
+    }
+
+    #[test]
+    fn test_rejects_illegal_records() {
+        assert!(GameRecord::parse("X A1\nO A1\n").is_err());
+        assert!(GameRecord::parse("[Seed 42]\n").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+#![allow(clippy::needless_return)]
+
+use crate::record::GameRecord;
+use crate::state::{GameState, Move};
+
+/// A cursor over a saved game that can be moved forwards and backwards one move at a time.
+pub struct Replay {
+    pub record: GameRecord,
+    position: usize,
+    game_state: GameState,
+}
+
+impl Replay {
+    pub fn new(record: GameRecord) -> Result<Self, &'static str> {
+        record.final_state()?;
+        let game_state = record.initial_state()?;
+        return Ok(Self { record, position: 0, game_state });
+    }
+
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        return Self::new(GameRecord::load(path)?);
+    }
+
+    /// How many moves have been played on the shown board.
+    pub fn position(&self) -> usize {
+        return self.position;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.record.moves.len();
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        return &self.game_state;
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        if self.position == 0 {
+            return None;
+        }
+        return Some(self.record.moves[self.position - 1]);
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        return self.position == self.len();
+    }
+
+    pub fn jump_to(&mut self, position: usize) {
+        self.position = position.min(self.len());
+        self.game_state = self.record.state_after(self.position).unwrap_or_else(|_| GameState::default_new());
+    }
+
+    pub fn forward(&mut self) {
+        self.jump_to(self.position + 1);
+    }
+
+    pub fn back(&mut self) {
+        self.jump_to(self.position.saturating_sub(1));
+    }
+
+    pub fn jump_to_start(&mut self) {
+        self.jump_to(0);
+    }
+
+    pub fn jump_to_end(&mut self) {
+        self.jump_to(self.len());
+    }
+}
+
+#[cfg(test)]
+mod replay_test {
+    use crate::players::Player::Order;
+    use crate::record::GameRecord;
+    use crate::replay::Replay;
+    use crate::tile::Tile::{Blue, Empty, Red};
+
+    #[test]
+    fn test_stepping_through_a_game() {
+        let mut record = GameRecord::new("A", "B", 1);
+        record.moves = vec![((0, 0), Red), ((1, 1), Blue), ((2, 2), Red)];
+        record.set_winner(Order);
+        let mut replay = Replay::new(record).expect("");
+        assert_eq!(replay.len(), 3);
+        assert_eq!(replay.last_move(), None);
+
+        replay.back();
+        assert_eq!(replay.position(), 0);
+        replay.forward();
+        replay.forward();
+        assert_eq!(replay.last_move(), Some(((1, 1), Blue)));
+        assert_eq!(replay.game_state().tile_at((2, 2)), Empty);
+
+        replay.jump_to_end();
+        assert!(replay.is_at_end());
+        assert_eq!(replay.game_state().tile_at((2, 2)), Red);
+        replay.forward();
+        assert_eq!(replay.position(), 3);
+
+        replay.jump_to_start();
+        assert_eq!(replay.game_state().tile_at((0, 0)), Empty);
+    }
+}
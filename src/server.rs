@@ -0,0 +1,538 @@
+#![allow(clippy::needless_return)]
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+use crate::config::parse_player;
+use crate::display::Renderer;
+use crate::engine::legal_moves;
+use crate::notation::{board_to_notation, move_fmt, parse_move};
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::record::{player_name, GameRecord};
+use crate::state::GameState;
+
+/// How many requests are answered at once. Updates requests do not hold a thread while they wait.
+const HTTP_THREADS: usize = 16;
+/// How long an updates request waits for a change before answering with the unchanged game.
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How many updates requests may wait at once; any more are turned away.
+const MAX_WAITING: usize = 256;
+
+/// Games hosted over HTTP, answered with JSON. Every game is kept as a `GameRecord`, so undo simply drops the last move.
+///
+/// ```text
+/// POST   /games                         creates a game
+/// GET    /games                         lists the games
+/// GET    /lobby                         the unfinished games with an open seat
+/// GET    /games/<id>                    board, turn player, result, open seats and version
+/// POST   /games/<id>/seats/<side>       claims the seat, answered with its token; sending {"token": ...} reclaims it
+/// DELETE /games/<id>/seats/<side>       gives up the seat, with {"token": ...}
+/// GET    /games/<id>/updates?since=<v>  waits until the game is past version v, then answers like GET /games/<id>
+/// POST   /games/<id>/moves              plays {"move": "X A1", "token": ...}
+/// GET    /games/<id>/legal-moves        every move the turn player may make
+/// POST   /games/<id>/undo               takes back the last move of an unfinished game, with {"token": ...} if its player holds a seat
+/// GET    /games/<id>/history            the moves so far and the game record
+/// ```
+///
+/// A move for a claimed seat needs that seat's token; an open seat can be played by anyone. The same goes for taking a move back,
+/// which is refused once the game is over.
+/// Errors are answered with a 4xx status and `{"error": "<message>"}`.
+#[derive(Default)]
+pub struct GameServer {
+    games: BTreeMap<u64, HostedGame>,
+    next_id: u64,
+}
+
+struct HostedGame {
+    record: GameRecord,
+    /// The tokens of the players holding the Order and Chaos seats.
+    seats: [Option<String>; 2],
+    /// Counts every change, so that spectators can wait for the next one.
+    version: u64,
+}
+
+impl HostedGame {
+    fn seat(&mut self, player: Player) -> &mut Option<String> {
+        match player {
+            Order => return &mut self.seats[0],
+            Chaos => return &mut self.seats[1]
+        }
+    }
+
+    fn open_seats(&self) -> Vec<&'static str> {
+        return [Order, Chaos].iter().zip(&self.seats).filter(|(_, token)| token.is_none()).map(|(player, _)| player_name(player)).collect();
+    }
+}
+
+#[derive(Serialize)]
+struct GameView {
+    id: u64,
+    board: String,
+    turn_player: &'static str,
+    result: Option<&'static str>,
+    moves: usize,
+    open_seats: Vec<&'static str>,
+    version: u64,
+}
+
+#[derive(Deserialize)]
+struct MoveRequest {
+    #[serde(rename = "move")]
+    played_move: String,
+    token: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct TokenRequest {
+    token: Option<String>,
+}
+
+type ApiResult = Result<(u16, Value), (u16, &'static str)>;
+
+impl GameServer {
+    /// Answers one request with a status code and a JSON body.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let path = path.split('?').next().unwrap_or_default();
+        let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+        let result = match (method, parts.as_slice()) {
+            ("POST", ["games"]) => self.create_game(),
+            ("GET", ["games"]) => Ok((200, json!({ "games": self.games.keys().collect::<Vec<&u64>>() }))),
+            ("GET", ["lobby"]) => Ok((200, self.lobby())),
+            ("GET", ["games", id]) | ("GET", ["games", id, "updates"]) => self.game(id).map(|(id, game)| (200, view(id, game))),
+            ("POST", ["games", id, "seats", side]) => self.claim_seat(id, side, body),
+            ("DELETE", ["games", id, "seats", side]) => self.leave_seat(id, side, body),
+            ("POST", ["games", id, "moves"]) => self.play(id, body),
+            ("GET", ["games", id, "legal-moves"]) => self.legal_moves(id),
+            ("POST", ["games", id, "undo"]) => self.undo(id, body),
+            ("GET", ["games", id, "history"]) => self.game(id).map(|(_, game)| (200, history(&game.record))),
+            (_, ["games", ..]) => Err((405, "Method not allowed")),
+            _ => Err((404, "Not found"))
+        };
+        match result {
+            Ok(v) => return v,
+            Err((status, message)) => return (status, json!({ "error": message }))
+        }
+    }
+
+    /// The version of the game at the given path, for requests that wait for a change.
+    pub fn version(&self, id: &str) -> Option<u64> {
+        return self.game(id).ok().map(|(_, game)| game.version);
+    }
+
+    fn create_game(&mut self) -> ApiResult {
+        self.next_id += 1;
+        let game = HostedGame { record: GameRecord::new("HTTP", "HTTP", 0), seats: [None, None], version: 0 };
+        let body = view(self.next_id, &game);
+        self.games.insert(self.next_id, game);
+        return Ok((201, body));
+    }
+
+    fn lobby(&self) -> Value {
+        let open: Vec<Value> = self.games.iter()
+            .filter(|(_, game)| !game.open_seats().is_empty() && result(&final_state(&game.record)).is_none())
+            .map(|(id, game)| view(*id, game))
+            .collect();
+        return json!({ "games": open });
+    }
+
+    fn game(&self, id: &str) -> Result<(u64, &HostedGame), (u16, &'static str)> {
+        let id: u64 = id.parse().map_err(|_| (404, "No such game"))?;
+        let game = self.games.get(&id).ok_or((404, "No such game"))?;
+        return Ok((id, game));
+    }
+
+    fn game_mut(&mut self, id: &str) -> Result<(u64, &mut HostedGame), (u16, &'static str)> {
+        let id: u64 = id.parse().map_err(|_| (404, "No such game"))?;
+        let game = self.games.get_mut(&id).ok_or((404, "No such game"))?;
+        return Ok((id, game));
+    }
+
+    fn claim_seat(&mut self, id: &str, side: &str, body: &str) -> ApiResult {
+        let player = parse_player(side).map_err(|_| (404, "Seats are order and chaos"))?;
+        let request = token_request(body)?;
+        let (id, game) = self.game_mut(id)?;
+        let seat = game.seat(player);
+        let status = match (seat.as_ref(), request.token) {
+            (Some(token), Some(claimed)) if *token == claimed => 200,
+            (Some(_), _) => return Err((409, "The seat is taken")),
+            (None, _) => {
+                *seat = Some(format!("{:016x}", rand::random::<u64>()));
+                201
+            }
+        };
+        let token = seat.clone();
+        game.version += 1;
+        return Ok((status, json!({ "seat": player_name(&player), "token": token, "game": view(id, game) })));
+    }
+
+    fn leave_seat(&mut self, id: &str, side: &str, body: &str) -> ApiResult {
+        let player = parse_player(side).map_err(|_| (404, "Seats are order and chaos"))?;
+        let request = token_request(body)?;
+        let (id, game) = self.game_mut(id)?;
+        let seat = game.seat(player);
+        if seat.is_none() || *seat != request.token {
+            return Err((403, "The seat is not yours"));
+        }
+        *seat = None;
+        game.version += 1;
+        return Ok((200, view(id, game)));
+    }
+
+    fn play(&mut self, id: &str, body: &str) -> ApiResult {
+        let request: MoveRequest = serde_json::from_str(body).map_err(|_| (400, "Expected a body like {\"move\": \"X A1\"}"))?;
+        let (coordinates, tile) = parse_move(&request.played_move).map_err(|v| (400, v))?;
+        let (id, game) = self.game_mut(id)?;
+        let mut game_state = final_state(&game.record);
+        if result(&game_state).is_some() {
+            return Err((409, "The game is over"));
+        }
+        let seat = game.seat(game_state.turn_player);
+        if seat.is_some() && *seat != request.token {
+            return Err((403, "It is not your turn"));
+        }
+        game_state.play(coordinates, tile).map_err(|v| (400, v))?;
+        game.record.moves.push((coordinates, tile));
+        if let Some(winner) = result(&game_state) {
+            game.record.set_tag("Result", winner);
+            log(&format!("Game {} won by {}\n{}", id, winner, Renderer::plain().board(&game_state)));
+        }
+        game.version += 1;
+        return Ok((200, view(id, game)));
+    }
+
+    fn legal_moves(&self, id: &str) -> ApiResult {
+        let (_, game) = self.game(id)?;
+        let game_state = final_state(&game.record);
+        let moves: Vec<String> = match result(&game_state) {
+            Some(_) => Vec::new(),
+            None => legal_moves(&game_state).iter().map(move_fmt).collect()
+        };
+        return Ok((200, json!({ "moves": moves })));
+    }
+
+    /// Takes back the last move for the player who made it, as long as the game is not over.
+    fn undo(&mut self, id: &str, body: &str) -> ApiResult {
+        let request = token_request(body)?;
+        let (id, game) = self.game_mut(id)?;
+        let game_state = final_state(&game.record);
+        if result(&game_state).is_some() {
+            return Err((409, "The game is over"));
+        }
+        if game.record.moves.is_empty() {
+            return Err((400, "There is no move to undo"));
+        }
+        let seat = game.seat(game_state.turn_player.opponent());
+        if seat.is_some() && *seat != request.token {
+            return Err((403, "Only the player who made the last move may undo it"));
+        }
+        game.record.moves.pop();
+        game.version += 1;
+        return Ok((200, view(id, game)));
+    }
+}
+
+fn token_request(body: &str) -> Result<TokenRequest, (u16, &'static str)> {
+    if body.trim().is_empty() {
+        return Ok(TokenRequest::default());
+    }
+    return serde_json::from_str(body).map_err(|_| (400, "Expected a body like {\"token\": \"...\"}"));
+}
+
+fn final_state(record: &GameRecord) -> GameState {
+    return record.final_state().unwrap_or_else(|_| GameState::default_new());
+}
+
+fn result(game_state: &GameState) -> Option<&'static str> {
+    if game_state.is_in_order() {
+        return Some("Order");
+    }
+    if !game_state.can_order_win() {
+        return Some("Chaos");
+    }
+    return None;
+}
+
+fn view(id: u64, game: &HostedGame) -> Value {
+    let game_state = final_state(&game.record);
+    let game_view = GameView {
+        id,
+        board: board_to_notation(&game_state),
+        turn_player: player_name(&game_state.turn_player),
+        result: result(&game_state),
+        moves: game.record.moves.len(),
+        open_seats: game.open_seats(),
+        version: game.version,
+    };
+    return serde_json::to_value(game_view).unwrap_or_default();
+}
+
+fn history(record: &GameRecord) -> Value {
+    let moves: Vec<String> = record.moves.iter().map(move_fmt).collect();
+    return json!({ "moves": moves, "record": record.to_text() });
+}
+
+/// The game and version an updates request waits on, if the request is one.
+fn awaited_version(method: &str, url: &str) -> Option<(String, u64)> {
+    let (path, query) = url.split_once('?')?;
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    match (method, parts.as_slice()) {
+        ("GET", ["games", id, "updates"]) => {
+            let since = query.split('&').find_map(|pair| pair.strip_prefix("since="))?.parse().ok()?;
+            return Some((id.to_string(), since));
+        }
+        _ => return None
+    }
+}
+
+/// An updates request parked until its game passes the version it has seen, or its time is up.
+struct Waiter {
+    request: Request,
+    id: String,
+    since: u64,
+    deadline: Instant,
+}
+
+/// The hosted games and the updates requests waiting on them.
+#[derive(Default)]
+struct Hosting {
+    games: GameServer,
+    waiting: Vec<Waiter>,
+}
+
+impl Hosting {
+    /// Takes the waiting requests whose game has changed or whose time is up, each with its answer.
+    fn take_ready(&mut self, now: Instant) -> Vec<(Request, (u16, Value))> {
+        let games = &mut self.games;
+        let (ready, waiting): (Vec<Waiter>, Vec<Waiter>) = std::mem::take(&mut self.waiting).into_iter()
+            .partition(|waiter| games.version(&waiter.id) != Some(waiter.since) || waiter.deadline <= now);
+        self.waiting = waiting;
+        return ready.into_iter().map(|waiter| {
+            let answer = games.handle("GET", &format!("/games/{}", waiter.id), "");
+            return (waiter.request, answer);
+        }).collect();
+    }
+}
+
+type Shared = (Mutex<Hosting>, Condvar);
+
+/// Serves the HTTP API on the given address and port until the process is stopped.
+///
+/// Requests are answered by several threads sharing one `GameServer`. Updates requests are parked until their game changes
+/// and answered by whichever thread changed it, or by a timer thread once they time out, so spectators do not hold up the players.
+pub fn run_http_server(bind: &str, port: u16) -> Result<(), &'static str> {
+    let server = Arc::new(Server::http((bind, port)).map_err(|_| "Could not listen on the port")?);
+    let shared: Arc<Shared> = Arc::new((Mutex::new(Hosting::default()), Condvar::new()));
+    let timer = {
+        let shared = shared.clone();
+        thread::spawn(move || expire_waiters(&shared))
+    };
+    let workers: Vec<_> = (0..HTTP_THREADS).map(|_| {
+        let server = server.clone();
+        let shared = shared.clone();
+        return thread::spawn(move || serve_requests(&server, &shared));
+    }).collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    let _ = timer.join();
+    return Ok(());
+}
+
+fn serve_requests(server: &Server, shared: &Shared) {
+    while let Ok(request) = server.recv() {
+        if answer(request, shared).is_err() {
+            return;
+        }
+    }
+}
+
+/// Answers one request, or parks it if it waits for a change that has not happened yet.
+fn answer(mut request: Request, shared: &Shared) -> Result<(), &'static str> {
+    let (lock, changed) = shared;
+    let mut body = String::new();
+    let _ = request.as_reader().read_to_string(&mut body);
+    let mut hosting = lock.lock().map_err(|_| "The games are no longer available")?;
+    if let Some((id, since)) = awaited_version(request.method().as_str(), request.url()) {
+        if hosting.games.version(&id) == Some(since) {
+            if hosting.waiting.len() >= MAX_WAITING {
+                drop(hosting);
+                respond(request, (503, json!({ "error": "Too many requests are waiting for updates" })));
+                return Ok(());
+            }
+            hosting.waiting.push(Waiter { request, id, since, deadline: Instant::now() + UPDATE_TIMEOUT });
+            changed.notify_all();
+            return Ok(());
+        }
+    }
+    let answer = hosting.games.handle(request.method().as_str(), request.url(), &body);
+    let ready = hosting.take_ready(Instant::now());
+    drop(hosting);
+    respond(request, answer);
+    for (waiter, answer) in ready {
+        respond(waiter, answer);
+    }
+    return Ok(());
+}
+
+/// Answers the parked updates requests whose time is up, sleeping until the next one is due.
+fn expire_waiters(shared: &Shared) {
+    let (lock, changed) = shared;
+    let Ok(mut hosting) = lock.lock() else { return };
+    loop {
+        let ready = hosting.take_ready(Instant::now());
+        if !ready.is_empty() {
+            drop(hosting);
+            for (request, answer) in ready {
+                respond(request, answer);
+            }
+            let Ok(guard) = lock.lock() else { return };
+            hosting = guard;
+            continue;
+        }
+        let woken = match hosting.waiting.iter().map(|waiter| waiter.deadline).min() {
+            Some(deadline) => changed.wait_timeout(hosting, deadline.saturating_duration_since(Instant::now())).ok().map(|(guard, _)| guard),
+            None => changed.wait(hosting).ok()
+        };
+        let Some(guard) = woken else { return };
+        hosting = guard;
+    }
+}
+
+/// Writes a line to the server's log on standard output, stamped with the time.
+fn log(line: &str) {
+    println!("{} {}", Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true), line);
+}
+
+/// Answers the request and logs it.
+fn respond(request: Request, (status, value): (u16, Value)) {
+    log(&format!("{} {} {}", request.method(), request.url(), status));
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    let response = Response::from_string(value.to_string()).with_status_code(status).with_header(content_type);
+    let _ = request.respond(response);
+}
+
+#[cfg(test)]
+mod server_test {
+    use std::sync::{Condvar, Mutex};
+    use serde_json::json;
+    use tiny_http::{Method, TestRequest};
+    use crate::server::{answer, awaited_version, GameServer, Hosting, Shared, MAX_WAITING};
+
+    #[test]
+    fn test_playing_through_the_api() {
+        let mut games = GameServer::default();
+        let (status, created) = games.handle("POST", "/games", "");
+        assert_eq!(status, 201);
+        assert_eq!(created["id"], 1);
+        assert_eq!(created["turn_player"], "Order");
+        assert_eq!(created["result"], json!(null));
+
+        let (status, played) = games.handle("POST", "/games/1/moves", r#"{"move": "X A1"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(played["board"], "X...../....../....../....../....../......");
+        assert_eq!(played["turn_player"], "Chaos");
+
+        let (_, legal) = games.handle("GET", "/games/1/legal-moves", "");
+        assert_eq!(legal["moves"].as_array().expect("").len(), 70);
+
+        let (_, history) = games.handle("GET", "/games/1/history", "");
+        assert_eq!(history["moves"], json!(["X A1"]));
+
+        let (status, undone) = games.handle("POST", "/games/1/undo", "");
+        assert_eq!(status, 200);
+        assert_eq!(undone["moves"], 0);
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let mut games = GameServer::default();
+        games.handle("POST", "/games", "");
+        games.handle("POST", "/games/1/moves", r#"{"move": "X A1"}"#);
+        assert_eq!(games.handle("POST", "/games/1/moves", r#"{"move": "O A1"}"#), (400, json!({ "error": "Space Already Occupied" })));
+        assert_eq!(games.handle("POST", "/games/1/moves", "X A1").0, 400);
+        assert_eq!(games.handle("GET", "/games/7", "").0, 404);
+        assert_eq!(games.handle("DELETE", "/games/1", "").0, 405);
+        games.handle("POST", "/games/1/undo", "");
+        assert_eq!(games.handle("POST", "/games/1/undo", "").0, 400);
+    }
+
+    #[test]
+    fn test_seats_lobby_and_reconnecting() {
+        let mut games = GameServer::default();
+        games.handle("POST", "/games", "");
+        games.handle("POST", "/games", "");
+        let (status, order) = games.handle("POST", "/games/1/seats/order", "");
+        assert_eq!(status, 201);
+        let order_token = order["token"].as_str().expect("").to_string();
+        assert_eq!(games.handle("POST", "/games/1/seats/order", "").0, 409);
+        let (_, chaos) = games.handle("POST", "/games/1/seats/chaos", "");
+        let chaos_token = chaos["token"].as_str().expect("").to_string();
+
+        let (_, lobby) = games.handle("GET", "/lobby", "");
+        assert_eq!(lobby["games"].as_array().expect("").len(), 1);
+        assert_eq!(lobby["games"][0]["id"], 2);
+
+        let move_by = |token: &str| json!({ "move": "X A1", "token": token }).to_string();
+        assert_eq!(games.handle("POST", "/games/1/moves", &move_by(&chaos_token)).0, 403);
+        assert_eq!(games.handle("POST", "/games/1/moves", r#"{"move": "X A1"}"#).0, 403);
+        assert_eq!(games.handle("POST", "/games/1/moves", &move_by(&order_token)).0, 200);
+        assert_eq!(games.handle("POST", "/games/1/undo", "").0, 403);
+        assert_eq!(games.handle("POST", "/games/1/undo", &json!({ "token": chaos_token }).to_string()).0, 403);
+
+        let reclaim = json!({ "token": order_token }).to_string();
+        let (status, reclaimed) = games.handle("POST", "/games/1/seats/order", &reclaim);
+        assert_eq!(status, 200);
+        assert_eq!(reclaimed["token"], order_token.as_str());
+        assert_eq!(reclaimed["game"]["moves"], 1);
+
+        assert_eq!(games.handle("DELETE", "/games/1/seats/chaos", &reclaim).0, 403);
+        let (_, left) = games.handle("DELETE", "/games/1/seats/chaos", &json!({ "token": chaos_token }).to_string());
+        assert_eq!(left["open_seats"], json!(["Chaos"]));
+        assert_eq!(left["version"], 5);
+    }
+
+    #[test]
+    fn test_updates_requests_wait_on_a_version() {
+        assert_eq!(awaited_version("GET", "/games/3/updates?since=7"), Some(("3".to_string(), 7)));
+        assert_eq!(awaited_version("GET", "/games/3/updates"), None);
+        assert_eq!(awaited_version("GET", "/games/3?since=7"), None);
+    }
+
+    #[test]
+    fn test_finished_games_cannot_be_undone() {
+        let mut games = GameServer::default();
+        games.handle("POST", "/games", "");
+        for played_move in ["X A1", "O F6", "X B1", "O F5", "X C1", "O F4", "X D1", "O F3", "X E1"] {
+            games.handle("POST", "/games/1/moves", &json!({ "move": played_move }).to_string());
+        }
+        assert_eq!(games.handle("GET", "/games/1", "").1["result"], "Order");
+        assert_eq!(games.handle("POST", "/games/1/undo", ""), (409, json!({ "error": "The game is over" })));
+    }
+
+    #[test]
+    fn test_updates_requests_are_parked_until_the_game_changes() {
+        let shared: Shared = (Mutex::new(Hosting::default()), Condvar::new());
+        let waiting = || shared.0.lock().expect("").waiting.len();
+        answer(TestRequest::new().with_method(Method::Post).with_path("/games").into(), &shared).expect("");
+        answer(TestRequest::new().with_path("/games/1/updates?since=0").into(), &shared).expect("");
+        answer(TestRequest::new().with_path("/games/1/updates?since=0").into(), &shared).expect("");
+        assert_eq!(waiting(), 2);
+        answer(TestRequest::new().with_path("/games/1/legal-moves").into(), &shared).expect("");
+        assert_eq!(waiting(), 2);
+        answer(TestRequest::new().with_method(Method::Post).with_path("/games/1/moves").with_body(r#"{"move": "X A1"}"#).into(), &shared).expect("");
+        assert_eq!(waiting(), 0);
+        answer(TestRequest::new().with_path("/games/1/updates?since=0").into(), &shared).expect("");
+        assert_eq!(waiting(), 0);
+
+        for _ in 0..MAX_WAITING + 1 {
+            answer(TestRequest::new().with_path("/games/1/updates?since=1").into(), &shared).expect("");
+        }
+        assert_eq!(waiting(), MAX_WAITING);
+    }
+}
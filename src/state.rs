@@ -1,25 +1,29 @@
-use std::{thread, time};
+#![allow(clippy::needless_return)]
+
 use Player::{Chaos, Order};
-use Tile::{Empty, Red};
+use Tile::{Blue, Empty, Red};
 use crate::players::Player;
-use crate::{random_coordinates, random_pawn};
 use crate::tile::Tile;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone)]
+/// A placement of a tile on the board, as taken by `GameState::play`.
+pub type Move = ((usize, usize), Tile);
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct GameState {
-    pub board: [[Tile; 6]; 6],
+    pub board: [[Tile; BOARD_SIZE]; BOARD_SIZE],
     pub turn_player: Player,
 }
 
 impl GameState {
     pub const fn default_new() -> Self {
         Self {
-            board: [[Empty; 6]; 6],
+            board: [[Empty; BOARD_SIZE]; BOARD_SIZE],
             turn_player: Order,
         }
     }
 
-    pub fn play(&mut self, coordinates: (usize, usize), play: Tile) -> Result<&str, &str> {
+    pub fn play(&mut self, coordinates: (usize, usize), play: Tile) -> Result<&'static str, &'static str> {
         match self.board[coordinates.0][coordinates.1] {
             Empty => self.board[coordinates.0][coordinates.1] = play,
             _ => return Err("Space Already Occupied")
@@ -32,22 +36,25 @@ impl GameState {
     }
 
     pub fn is_in_order(&self) -> bool {
-        for row in 0..6 {
-            if self.is_row_in_order(row) {
+        for line in LINES {
+            if self.is_line_in_order(&line) {
                 return true;
             }
         }
-        for column in 0..6 {
-            if self.is_column_in_order(column) {
-                return true;
+        return false;
+    }
+
+    /// The five cells of the line that is in order, if there is one.
+    pub fn winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        let line = LINES.iter().find(|line| self.is_line_in_order(line))?;
+        for start in 0..=line.length - WINNING_LENGTH {
+            let cells: Vec<(usize, usize)> = (start..start + WINNING_LENGTH).map(|index| line.cell(index)).collect();
+            let tile = self.tile_at(cells[0]);
+            if !Empty.eq(&tile) && cells.iter().all(|cell| self.tile_at(*cell) == tile) {
+                return Some(cells);
             }
         }
-        return self.is_down_diagonal_in_order(0, 0) ||
-            self.is_down_diagonal_in_order(0, 1) ||
-            self.is_down_diagonal_in_order(1, 0) ||
-            self.is_up_diagonal_in_order(4, 0) ||
-            self.is_up_diagonal_in_order(5, 0) ||
-            self.is_up_diagonal_in_order(5, 1);
+        return None;
     }
 
     pub fn can_order_win(&self) -> bool {
@@ -62,61 +69,139 @@ impl GameState {
         return false;
     }
 
-    fn is_row_in_order(&self, row: usize) -> bool {
-        let mut current_tile = Red;
-        let mut streak = 0;
-        let mut order_wins = false;
-        for tile in self.board[row] {
-            visit_tile_for_order(&mut current_tile, &tile, &mut streak, &mut order_wins);
+    pub fn tile_at(&self, coordinates: (usize, usize)) -> Tile {
+        return self.board[coordinates.0][coordinates.1];
+    }
+
+    pub fn empty_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (row_index, row) in self.board.iter().enumerate() {
+            for (column_index, tile) in row.iter().enumerate() {
+                if Empty.eq(tile) {
+                    cells.push((row_index, column_index));
+                }
+            }
         }
-        return order_wins;
+        return cells;
     }
 
-    fn is_column_in_order(&self, column: usize) -> bool {
-        let mut current_tile = Red;
-        let mut streak = 0;
-        let mut order_wins = false;
-        for row in self.board {
-            let tile = row[column];
-            visit_tile_for_order(&mut current_tile, &tile, &mut streak, &mut order_wins);
+    /// Every move that would complete exactly five in a row, and so win the game for Order.
+    pub fn winning_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for cell in self.empty_cells() {
+            for tile in [Red, Blue] {
+                let mut next_state = *self;
+                let _ = next_state.play(cell, tile);
+                if next_state.is_in_order() {
+                    moves.push((cell, tile));
+                }
+            }
         }
-        return order_wins;
+        return moves;
     }
 
-    fn is_down_diagonal_in_order(&self, row: usize, column: usize) -> bool {
-        let mut current_tile = Red;
-        let mut streak = 0;
-        let mut order_wins = false;
-        let mut offset = 0;
-        while row + offset < self.board.len() && column + offset < self.board[0].len() {
-            let tile = self.board[row + offset][column + offset];
-            visit_tile_for_order(&mut current_tile, &tile, &mut streak, &mut order_wins);
-            offset += 1;
+    /// The wins Order threatens for its next turn, and what Chaos can do about them.
+    pub fn threats(&self) -> Threats {
+        let winning_moves = self.winning_moves();
+        let mut forced_cells: Vec<(usize, usize)> = winning_moves.iter().map(|(cell, _)| *cell).collect();
+        forced_cells.dedup();
+        let mut saving_moves = Vec::new();
+        if Chaos.eq(&self.turn_player) && !winning_moves.is_empty() {
+            for cell in self.empty_cells() {
+                for tile in [Red, Blue] {
+                    let mut next_state = *self;
+                    let _ = next_state.play(cell, tile);
+                    if !next_state.is_in_order() && next_state.winning_moves().is_empty() {
+                        saving_moves.push((cell, tile));
+                    }
+                }
+            }
         }
-        return order_wins;
+        return Threats { winning_moves, forced_cells, saving_moves };
     }
 
-    fn is_up_diagonal_in_order(&self, row: usize, column: usize) -> bool {
+    fn is_line_in_order(&self, line: &Line) -> bool {
         let mut current_tile = Red;
         let mut streak = 0;
         let mut order_wins = false;
-        let mut offset = 0;
-        while row >= offset && column + offset < self.board[0].len() {
-            let tile = self.board[row - offset][column + offset];
+        for index in 0..line.length {
+            let tile = self.tile_at(line.cell(index));
             visit_tile_for_order(&mut current_tile, &tile, &mut streak, &mut order_wins);
-            offset += 1;
         }
         return order_wins;
     }
 }
 
+/// Immediate winning chances for Order. When Chaos is to move, `forced_cells` are the cells it has
+/// to neutralise and `saving_moves` lists every move that stops all threats at once.
+#[derive(Clone)]
+pub struct Threats {
+    pub winning_moves: Vec<Move>,
+    pub forced_cells: Vec<(usize, usize)>,
+    pub saving_moves: Vec<Move>,
+}
+
+impl Threats {
+    pub fn is_empty(&self) -> bool {
+        return self.winning_moves.is_empty();
+    }
+
+    pub fn can_chaos_neutralise_all(&self) -> bool {
+        return self.is_empty() || !self.saving_moves.is_empty();
+    }
+}
+
+/// A row, column or diagonal of the board that is long enough to hold five in a row.
+#[derive(Copy, Clone)]
+pub struct Line {
+    pub start: (usize, usize),
+    pub direction: (isize, isize),
+    pub length: usize,
+}
+
+impl Line {
+    const fn new(start: (usize, usize), direction: (isize, isize), length: usize) -> Self {
+        Self { start, direction, length }
+    }
+
+    pub fn cell(&self, index: usize) -> (usize, usize) {
+        let row = self.start.0 as isize + self.direction.0 * index as isize;
+        let column = self.start.1 as isize + self.direction.1 * index as isize;
+        return (row as usize, column as usize);
+    }
+}
+
+pub const BOARD_SIZE: usize = 6;
+pub const WINNING_LENGTH: usize = 5;
+
+pub const LINES: [Line; 18] = [
+    Line::new((0, 0), (0, 1), 6),
+    Line::new((1, 0), (0, 1), 6),
+    Line::new((2, 0), (0, 1), 6),
+    Line::new((3, 0), (0, 1), 6),
+    Line::new((4, 0), (0, 1), 6),
+    Line::new((5, 0), (0, 1), 6),
+    Line::new((0, 0), (1, 0), 6),
+    Line::new((0, 1), (1, 0), 6),
+    Line::new((0, 2), (1, 0), 6),
+    Line::new((0, 3), (1, 0), 6),
+    Line::new((0, 4), (1, 0), 6),
+    Line::new((0, 5), (1, 0), 6),
+    Line::new((0, 0), (1, 1), 6),
+    Line::new((0, 1), (1, 1), 5),
+    Line::new((1, 0), (1, 1), 5),
+    Line::new((5, 0), (-1, 1), 6),
+    Line::new((4, 0), (-1, 1), 5),
+    Line::new((5, 1), (-1, 1), 5),
+];
+
 fn visit_tile_for_order(current_tile: &mut Tile, visit: &Tile, streak: &mut i32, order_wins: &mut bool) {
     if current_tile.eq(&visit) {
         *streak += 1;
-    } else if Empty.eq(&visit) {
+    } else if Empty.eq(visit) {
         *streak = 0;
     } else {
-        *current_tile = visit.clone();
+        *current_tile = *visit;
         *streak = 1;
     }
     if *streak == 5 {
@@ -127,19 +212,12 @@ fn visit_tile_for_order(current_tile: &mut Tile, visit: &Tile, streak: &mut i32,
     }
 }
 
-pub fn make_a_random_move(game_state: &mut GameState) {
-    let mut played: bool = false;
-    thread::sleep(time::Duration::from_secs(3));
-    while !played {
-        let pawn = random_pawn();
-        let coordinates = random_coordinates();
-        let result = game_state.play(coordinates, pawn);
-        played = result.is_ok();
-    }
-}
-
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod state_test {
+    use crate::display::Renderer;
+    use crate::notation::parse_diagram;
+    use crate::players::Player::{Chaos, Order};
     use crate::state::GameState;
     use crate::tile::Tile::{Blue, Red};
 
@@ -151,7 +229,7 @@ mod state_test {
         game_state.play((0, 3), Blue).expect("");
         game_state.play((0, 4), Blue).expect("");
         game_state.play((0, 5), Blue).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), true);
     }
@@ -165,7 +243,7 @@ mod state_test {
         game_state.play((0, 3), Blue).expect("");
         game_state.play((0, 4), Blue).expect("");
         game_state.play((0, 5), Blue).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), false);
     }
@@ -178,7 +256,7 @@ mod state_test {
         game_state.play((3, 1), Blue).expect("");
         game_state.play((4, 1), Blue).expect("");
         game_state.play((5, 1), Blue).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), true);
     }
@@ -192,7 +270,7 @@ mod state_test {
         game_state.play((3, 1), Red).expect("");
         game_state.play((4, 1), Red).expect("");
         game_state.play((5, 1), Red).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), false);
     }
@@ -205,7 +283,7 @@ mod state_test {
         game_state.play((3, 3), Blue).expect("");
         game_state.play((4, 4), Blue).expect("");
         game_state.play((5, 5), Blue).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), true);
     }
@@ -219,7 +297,7 @@ mod state_test {
         game_state.play((3, 3), Blue).expect("");
         game_state.play((4, 4), Blue).expect("");
         game_state.play((5, 5), Blue).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), false);
     }
@@ -232,7 +310,7 @@ mod state_test {
         game_state.play((3, 2), Red).expect("");
         game_state.play((4, 1), Red).expect("");
         game_state.play((5, 0), Red).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), true);
     }
@@ -246,7 +324,7 @@ mod state_test {
         game_state.play((3, 2), Red).expect("");
         game_state.play((4, 1), Red).expect("");
         game_state.play((5, 0), Red).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), false);
     }
@@ -264,7 +342,7 @@ mod state_test {
         game_state.play((3, 1), Blue).expect("");
         game_state.play((4, 1), Blue).expect("");
         game_state.play((5, 1), Blue).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), true);
     }
@@ -282,8 +360,61 @@ mod state_test {
         game_state.play((2, 1), Red).expect("");
         game_state.play((3, 1), Red).expect("");
         game_state.play((4, 1), Red).expect("");
-        println!("{}", game_state);
+        println!("{}", Renderer::plain().board(&game_state));
         println!("Order Won: {}", game_state.is_in_order());
         assert_eq!(game_state.is_in_order(), true);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_threats_for_chaos_to_neutralise() {
+        let mut game_state: GameState = GameState::default_new();
+        game_state.play((2, 0), Red).expect("");
+        game_state.play((2, 1), Red).expect("");
+        game_state.play((2, 2), Red).expect("");
+        game_state.play((2, 3), Red).expect("");
+        assert!(game_state.turn_player == Order);
+        assert_eq!(game_state.winning_moves(), vec![((2, 4), Red)]);
+
+        game_state.play((5, 5), Blue).expect("");
+        let threats = game_state.threats();
+        assert!(game_state.turn_player == Chaos);
+        assert_eq!(threats.forced_cells, vec![(2, 4)]);
+        assert!(threats.saving_moves.contains(&((2, 4), Blue)));
+        assert!(threats.saving_moves.contains(&((2, 5), Red)));
+        assert!(!threats.saving_moves.contains(&((2, 4), Red)));
+        assert_eq!(threats.can_chaos_neutralise_all(), true);
+    }
+
+    #[test]
+    fn test_threats_chaos_cannot_stop() {
+        let mut game_state: GameState = GameState::default_new();
+        game_state.play((1, 1), Red).expect("");
+        game_state.play((1, 2), Red).expect("");
+        game_state.play((1, 3), Red).expect("");
+        game_state.play((1, 4), Red).expect("");
+        game_state.play((4, 1), Blue).expect("");
+        game_state.play((4, 2), Blue).expect("");
+        game_state.play((4, 3), Blue).expect("");
+        game_state.play((4, 4), Blue).expect("");
+        game_state.play((0, 0), Red).expect("");
+        let threats = game_state.threats();
+        assert!(game_state.turn_player == Chaos);
+        assert_eq!(threats.forced_cells.len(), 4);
+        assert_eq!(threats.can_chaos_neutralise_all(), false);
+    }
+
+    #[test]
+    fn test_winning_line_on_the_anti_diagonal() {
+        let game_state = parse_diagram("
+              A B C D E F
+            1 . . . . . X
+            2 . . . . X .
+            3 . . . X . .
+            4 . . X . . .
+            5 . X . . . .
+            6 O . . . . .
+        ").expect("");
+        assert_eq!(game_state.is_in_order(), true);
+        assert_eq!(game_state.winning_line(), Some(vec![(4, 1), (3, 2), (2, 3), (1, 4), (0, 5)]));
+    }
+}
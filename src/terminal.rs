@@ -0,0 +1,65 @@
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::io::{stdout, IsTerminal};
+
+/// What the terminal the game writes to can show.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    pub color: bool,
+    pub emoji: bool,
+}
+
+impl Capabilities {
+    /// Everything, for when the user asks for colour regardless of the terminal.
+    pub fn full() -> Self {
+        return Self { color: true, emoji: true };
+    }
+
+    /// Looks at standard output and the environment of this process.
+    pub fn detect() -> Self {
+        return Self::from_environment(|name| env::var(name).ok(), stdout().is_terminal());
+    }
+
+    /// Colour needs a terminal that is not `TERM=dumb` and no `NO_COLOR` (see no-color.org).
+    /// Emoji are only drawn on top of colour, and need a UTF-8 locale or, on Windows, Windows Terminal.
+    pub fn from_environment(variable: impl Fn(&str) -> Option<String>, is_terminal: bool) -> Self {
+        let set = |name: &str| variable(name).filter(|value| !value.is_empty());
+        let dumb = set("TERM").is_some_and(|term| term == "dumb");
+        let color = is_terminal && !dumb && set("NO_COLOR").is_none();
+        // The first locale variable that is set decides, as it does for the C library.
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|name| set(name));
+        let unicode = match locale {
+            Some(locale) => {
+                let locale = locale.to_lowercase();
+                locale.contains("utf-8") || locale.contains("utf8")
+            }
+            None => cfg!(windows) && set("WT_SESSION").is_some()
+        };
+        return Self { color, emoji: color && unicode };
+    }
+}
+
+#[cfg(test)]
+mod terminal_test {
+    use std::collections::HashMap;
+    use crate::terminal::Capabilities;
+
+    fn capabilities(variables: &[(&str, &str)], is_terminal: bool) -> Capabilities {
+        let variables: HashMap<String, String> = variables.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        return Capabilities::from_environment(|name| variables.get(name).cloned(), is_terminal);
+    }
+
+    #[test]
+    fn test_detection() {
+        let utf8 = ("LANG", "en_GB.UTF-8");
+        assert_eq!(capabilities(&[utf8, ("TERM", "xterm-256color")], true), Capabilities::full());
+        assert_eq!(capabilities(&[utf8], false), Capabilities { color: false, emoji: false });
+        assert_eq!(capabilities(&[utf8, ("NO_COLOR", "1")], true), Capabilities { color: false, emoji: false });
+        assert_eq!(capabilities(&[utf8, ("NO_COLOR", "")], true), Capabilities::full());
+        assert_eq!(capabilities(&[utf8, ("TERM", "dumb")], true), Capabilities { color: false, emoji: false });
+        assert_eq!(capabilities(&[("LANG", "C")], true), Capabilities { color: true, emoji: false });
+        assert_eq!(capabilities(&[("LC_ALL", "C"), utf8], true), Capabilities { color: true, emoji: false });
+        assert_eq!(capabilities(&[("LC_CTYPE", "de_DE.utf8"), ("LANG", "C")], true), Capabilities::full());
+    }
+}
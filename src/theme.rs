@@ -0,0 +1,198 @@
+#![allow(clippy::needless_return)]
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::players::Player;
+use crate::players::Player::{Chaos, Order};
+use crate::tile::Tile;
+
+/// The eight colours every terminal knows, in the order of their ANSI escape codes.
+const COLOR_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+/// How the window draws the eight terminal colours.
+const PALETTE: [(u8, u8, u8); 8] = [(0, 0, 0), (255, 0, 0), (51, 153, 0), (204, 170, 0), (0, 0, 255), (255, 25, 217), (0, 170, 204), (255, 255, 255)];
+
+/// A colour of a theme, written as one of the eight terminal colour names or as `#rrggbb`.
+/// Named colours use the terminal's own palette; the others need a terminal with 24-bit colour.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ThemeColor {
+    Named(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl ThemeColor {
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let text = text.trim().to_lowercase();
+        if let Some(index) = COLOR_NAMES.iter().position(|name| *name == text) {
+            return Ok(ThemeColor::Named(index as u8));
+        }
+        let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6).ok_or("A colour is a name such as yellow or a value such as #ccaa00")?;
+        let channel = |start: usize| u8::from_str_radix(&hex[start..start + 2], 16).map_err(|_| "A colour is a name such as yellow or a value such as #ccaa00");
+        return Ok(ThemeColor::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            ThemeColor::Named(index) => PALETTE[index as usize % PALETTE.len()],
+            ThemeColor::Rgb(red, green, blue) => (red, green, blue)
+        }
+    }
+
+    /// The parameters of the escape sequence that sets this as the text colour.
+    pub fn foreground(&self) -> String {
+        match *self {
+            ThemeColor::Named(index) => format!("3{}", index),
+            ThemeColor::Rgb(red, green, blue) => format!("38;2;{};{};{}", red, green, blue)
+        }
+    }
+
+    /// The parameters of the escape sequence that sets this as the background colour.
+    pub fn background(&self) -> String {
+        match *self {
+            ThemeColor::Named(index) => format!("4{}", index),
+            ThemeColor::Rgb(red, green, blue) => format!("48;2;{};{};{}", red, green, blue)
+        }
+    }
+}
+
+impl fmt::Display for ThemeColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ThemeColor::Named(index) => write!(f, "{}", COLOR_NAMES[index as usize % COLOR_NAMES.len()]),
+            ThemeColor::Rgb(red, green, blue) => write!(f, "#{:02x}{:02x}{:02x}", red, green, blue)
+        }
+    }
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = &'static str;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        return Self::parse(&text);
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        return color.to_string();
+    }
+}
+
+/// The colours both front ends draw with. A user theme in the configuration file only needs the colours it changes:
+///
+/// ```toml
+/// theme = "dusk"
+///
+/// [themes.dusk]
+/// order = "#ff9900"
+/// highlight = "magenta"
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub order: ThemeColor,
+    pub chaos: ThemeColor,
+    /// The X pieces.
+    pub x: ThemeColor,
+    /// The O pieces.
+    pub o: ThemeColor,
+    /// Empty cells.
+    pub board: ThemeColor,
+    /// Threats, hints and mistakes.
+    pub highlight: ThemeColor,
+}
+
+pub const BUILT_IN_THEMES: [&str; 4] = ["default", "high-contrast", "colour-blind-safe", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        return Self {
+            order: ThemeColor::Named(3),
+            chaos: ThemeColor::Named(2),
+            x: ThemeColor::Named(1),
+            o: ThemeColor::Named(4),
+            board: ThemeColor::Named(7),
+            highlight: ThemeColor::Rgb(230, 60, 20),
+        };
+    }
+}
+
+impl Theme {
+    pub fn built_in(name: &str) -> Option<Self> {
+        match name {
+            "default" => return Some(Self::default()),
+            "high-contrast" => return Some(Self {
+                order: ThemeColor::Rgb(255, 255, 0),
+                chaos: ThemeColor::Rgb(0, 255, 255),
+                x: ThemeColor::Rgb(200, 0, 0),
+                o: ThemeColor::Rgb(0, 0, 200),
+                board: ThemeColor::Named(0),
+                highlight: ThemeColor::Rgb(255, 0, 255),
+            }),
+            // The Okabe-Ito palette, which stays distinct with every common form of colour blindness.
+            "colour-blind-safe" => return Some(Self {
+                order: ThemeColor::Rgb(230, 159, 0),
+                chaos: ThemeColor::Rgb(86, 180, 233),
+                x: ThemeColor::Rgb(213, 94, 0),
+                o: ThemeColor::Rgb(0, 114, 178),
+                board: ThemeColor::Named(7),
+                highlight: ThemeColor::Rgb(204, 121, 167),
+            }),
+            "monochrome" => return Some(Self {
+                order: ThemeColor::Named(7),
+                chaos: ThemeColor::Rgb(128, 128, 128),
+                x: ThemeColor::Named(0),
+                o: ThemeColor::Named(0),
+                board: ThemeColor::Named(0),
+                highlight: ThemeColor::Named(7),
+            }),
+            _ => return None
+        }
+    }
+
+    pub fn player(&self, player: &Player) -> ThemeColor {
+        match player {
+            Order => self.order,
+            Chaos => self.chaos
+        }
+    }
+
+    pub fn tile(&self, tile: &Tile) -> ThemeColor {
+        match tile {
+            Tile::Red => self.x,
+            Tile::Blue => self.o,
+            Tile::Empty => self.board
+        }
+    }
+}
+
+#[cfg(test)]
+mod theme_test {
+    use std::collections::BTreeMap;
+    use crate::theme::{Theme, ThemeColor, BUILT_IN_THEMES};
+
+    #[test]
+    fn test_colours() {
+        assert_eq!(ThemeColor::parse("Yellow"), Ok(ThemeColor::Named(3)));
+        assert_eq!(ThemeColor::parse("#CCAA00"), Ok(ThemeColor::Rgb(204, 170, 0)));
+        assert!(ThemeColor::parse("#ccaa").is_err());
+        assert!(ThemeColor::parse("mauve").is_err());
+        assert_eq!(ThemeColor::Named(3).foreground(), "33");
+        assert_eq!(ThemeColor::Rgb(1, 2, 3).background(), "48;2;1;2;3");
+        assert_eq!(ThemeColor::Named(3).rgb(), (204, 170, 0));
+        assert_eq!(ThemeColor::Rgb(230, 60, 20).to_string(), "#e63c14");
+    }
+
+    #[test]
+    fn test_user_themes_fill_in_from_the_default() {
+        let themes: BTreeMap<String, Theme> = toml::from_str("[dusk]\norder = \"#ff9900\"\nhighlight = \"magenta\"\n").expect("");
+        let dusk = themes["dusk"];
+        assert_eq!(dusk.order, ThemeColor::Rgb(255, 153, 0));
+        assert_eq!(dusk.highlight, ThemeColor::Named(5));
+        assert_eq!(dusk.chaos, Theme::default().chaos);
+        assert!(toml::from_str::<Theme>("x = \"plaid\"").is_err());
+        for name in BUILT_IN_THEMES {
+            assert!(Theme::built_in(name).is_some());
+        }
+    }
+}
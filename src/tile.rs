@@ -1,7 +1,13 @@
-#[derive(Copy, Clone, Eq, PartialEq)]
-pub enum Tile {
-    Blue,
-    Red,
-    Empty,
-}
-
+use serde::{Deserialize, Serialize};
+
+/// Serialised with the letters of the move notation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Tile {
+    #[serde(rename = "O")]
+    Blue,
+    #[serde(rename = "X")]
+    Red,
+    #[serde(rename = ".")]
+    Empty,
+}
+